@@ -0,0 +1,495 @@
+//! Optional localhost HTTP/JSON API for driving giagui from other tools.
+//!
+//! Every request needs an `Authorization: Bearer <token>` header. Endpoints:
+//!
+//! - `GET /status` - form state and whether gia is executing
+//! - `POST /state` - set any of prompt, options, model, task, role and the flags
+//! - `POST /execute` - send the current form, optionally `{"with_audio": true}`
+//!   and template placeholder values as `{"values": {"name": "value"}}`. Answers
+//!   409 when gia is busy or a placeholder has no value
+//! - `GET /result` - the last gia result including stdout, stderr and timing
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::net::Ipv4Addr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::config::config_dir;
+use crate::gia::GiaResult;
+
+pub const TOKEN_ENV: &str = "GIAGUI_API_TOKEN";
+
+/// How long `POST /execute` waits for the window to start the request. A
+/// minimized window may not get to it in time; the request is then queued.
+const EXECUTE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Partial update of the form; missing fields are left unchanged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateUpdate {
+    pub prompt: Option<String>,
+    pub options: Option<String>,
+    pub model: Option<String>,
+    pub task: Option<String>,
+    pub role: Option<String>,
+    pub use_clipboard: Option<bool>,
+    pub browser_output: Option<bool>,
+    pub resume: Option<bool>,
+    pub tts_enabled: Option<bool>,
+    pub tts_language: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ExecuteBody {
+    with_audio: bool,
+    values: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum ApiCommand {
+    SetState(StateUpdate),
+    /// `reply` gets whether gia was started, or why not.
+    Execute {
+        with_audio: bool,
+        values: HashMap<String, String>,
+        reply: mpsc::Sender<Result<(), String>>,
+    },
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ApiStatus {
+    pub is_executing: bool,
+    pub pending_commands: usize,
+    pub prompt: String,
+    pub options: String,
+    pub model: String,
+    pub task: String,
+    pub role: String,
+    pub use_clipboard: bool,
+    pub browser_output: bool,
+    pub resume: bool,
+    pub tts_enabled: bool,
+    pub tts_language: String,
+}
+
+#[derive(Default)]
+struct Shared {
+    status: ApiStatus,
+    last_result: Option<GiaResult>,
+}
+
+/// UI-side handle: commands flow in, status and results flow out.
+pub struct ApiServer {
+    pub port: u16,
+    commands: Arc<Mutex<VecDeque<ApiCommand>>>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl ApiServer {
+    pub fn start(
+        port: u16,
+        token: String,
+        is_executing: Arc<Mutex<bool>>,
+        ctx: egui::Context,
+    ) -> Result<ApiServer, String> {
+        let server = Server::http((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| format!("Failed to start API server on port {}: {}", port, e))?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .unwrap_or(port);
+
+        let api = ApiServer {
+            port,
+            commands: Arc::default(),
+            shared: Arc::default(),
+        };
+        let commands = Arc::clone(&api.commands);
+        let shared = Arc::clone(&api.shared);
+
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let response = handle(
+                    &mut request,
+                    &token,
+                    &commands,
+                    &shared,
+                    &is_executing,
+                    &ctx,
+                );
+                let _ = request.respond(response);
+            }
+        });
+
+        Ok(api)
+    }
+
+    pub fn take_commands(&self) -> Vec<ApiCommand> {
+        self.commands.lock().unwrap().drain(..).collect()
+    }
+
+    pub fn publish_status(&self, status: ApiStatus) {
+        self.shared.lock().unwrap().status = status;
+    }
+
+    pub fn publish_result(&self, result: &GiaResult) {
+        self.shared.lock().unwrap().last_result = Some(result.clone());
+    }
+}
+
+fn handle(
+    request: &mut Request,
+    token: &str,
+    commands: &Mutex<VecDeque<ApiCommand>>,
+    shared: &Mutex<Shared>,
+    is_executing: &Mutex<bool>,
+    ctx: &egui::Context,
+) -> Response<Cursor<Vec<u8>>> {
+    let expected = format!("Bearer {}", token);
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization") && same_token(header.value.as_str(), &expected)
+    });
+    if !authorized {
+        return error(401, "missing or invalid token");
+    }
+
+    let mut body = String::new();
+    if request.as_reader().read_to_string(&mut body).is_err() {
+        return error(400, "unreadable request body");
+    }
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let command = match (request.method(), path.as_str()) {
+        (Method::Get, "/status") => {
+            let mut status = shared.lock().unwrap().status.clone();
+            status.is_executing = *is_executing.lock().unwrap();
+            status.pending_commands = commands.lock().unwrap().len();
+            return json(200, &status);
+        }
+        (Method::Get, "/result") => {
+            return match &shared.lock().unwrap().last_result {
+                Some(result) => json(200, result),
+                None => error(404, "no result yet"),
+            };
+        }
+        (Method::Post, "/state") => match parse_body::<StateUpdate>(&body) {
+            Ok(update) => ApiCommand::SetState(update),
+            Err(e) => return error(400, &e),
+        },
+        (Method::Post, "/execute") => {
+            if *is_executing.lock().unwrap() {
+                return error(409, "gia is already executing");
+            }
+            let body = match parse_body::<ExecuteBody>(&body) {
+                Ok(body) => body,
+                Err(e) => return error(400, &e),
+            };
+            let (reply, outcome) = mpsc::channel();
+            commands.lock().unwrap().push_back(ApiCommand::Execute {
+                with_audio: body.with_audio,
+                values: body.values,
+                reply,
+            });
+            ctx.request_repaint();
+            return match outcome.recv_timeout(EXECUTE_TIMEOUT) {
+                Ok(Ok(())) => json(202, &serde_json::json!({ "started": true })),
+                Ok(Err(e)) => error(409, &e),
+                Err(RecvTimeoutError::Timeout) => json(202, &serde_json::json!({ "queued": true })),
+                Err(RecvTimeoutError::Disconnected) => error(500, "request was dropped"),
+            };
+        }
+        _ => return error(404, "unknown endpoint"),
+    };
+
+    commands.lock().unwrap().push_back(command);
+    ctx.request_repaint();
+    json(202, &serde_json::json!({ "queued": true }))
+}
+
+/// Compares in constant time so the token cannot be guessed byte by byte.
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn parse_body<T: Default + for<'de> Deserialize<'de>>(body: &str) -> Result<T, String> {
+    if body.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(body).map_err(|e| format!("invalid JSON body: {}", e))
+}
+
+fn json<T: Serialize>(status: u16, value: &T) -> Response<Cursor<Vec<u8>>> {
+    let body = serde_json::to_string_pretty(value).unwrap_or_default();
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+fn error(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+/// Token from the environment, or the one stored in the config directory
+/// (generated on first use).
+pub fn load_token() -> Result<String, String> {
+    if let Ok(token) = std::env::var(TOKEN_ENV)
+        && !token.trim().is_empty()
+    {
+        return Ok(token.trim().to_string());
+    }
+
+    let path = config_dir()
+        .ok_or("No config directory available")?
+        .join("api-token");
+    if let Ok(token) = fs::read_to_string(&path)
+        && !token.trim().is_empty()
+    {
+        return Ok(token.trim().to_string());
+    }
+
+    let token = generate_token()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut options = fs::File::options();
+    options.write(true).create(true).truncate(true);
+    // Readable by the owner only from the moment it exists
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(token.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(token)
+}
+
+/// 256 random bits from the operating system, hex encoded.
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate API token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::gia::{self, GiaRequest};
+    use std::io::Read;
+    use std::net::TcpStream;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    const TOKEN: &str = "test-token";
+
+    /// A stand-in for gia that echoes its arguments, in a directory deleted
+    /// with the returned guard.
+    fn fake_gia() -> (TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gia");
+        fs::write(&path, "#!/bin/sh\necho \"fake gia: $*\"\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        let program = path.display().to_string();
+        (dir, program)
+    }
+
+    fn start() -> (ApiServer, Arc<Mutex<bool>>) {
+        let is_executing = Arc::new(Mutex::new(false));
+        let api = ApiServer::start(
+            0,
+            TOKEN.to_string(),
+            Arc::clone(&is_executing),
+            egui::Context::default(),
+        )
+        .unwrap();
+        (api, is_executing)
+    }
+
+    /// Sends one HTTP request and returns the status code and body.
+    fn call(port: u16, method: &str, path: &str, token: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            token,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    /// Sends a request from another thread, as a client would while the
+    /// window keeps running.
+    fn call_async(
+        port: u16,
+        method: &'static str,
+        path: &'static str,
+        body: &'static str,
+    ) -> thread::JoinHandle<(u16, String)> {
+        thread::spawn(move || call(port, method, path, TOKEN, body))
+    }
+
+    /// Waits for the server thread to queue a command, like the UI loop does.
+    fn next_command(api: &ApiServer) -> ApiCommand {
+        let started = Instant::now();
+        loop {
+            if let Some(command) = api.take_commands().into_iter().next() {
+                return command;
+            }
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "no command queued"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn rejects_requests_without_the_token() {
+        let (api, _) = start();
+        for (method, path) in [
+            ("GET", "/status"),
+            ("GET", "/result"),
+            ("POST", "/state"),
+            ("POST", "/execute"),
+        ] {
+            let (status, body) = call(api.port, method, path, "wrong", "");
+            assert_eq!(status, 401, "{} {}: {}", method, path, body);
+            let (status, _) = call(api.port, method, path, &format!("{}x", TOKEN), "");
+            assert_eq!(status, 401, "{} {}", method, path);
+        }
+        assert!(api.take_commands().is_empty());
+    }
+
+    #[test]
+    fn reports_bad_requests() {
+        let (api, _) = start();
+        let port = api.port;
+        assert_eq!(call(port, "GET", "/result", TOKEN, "").0, 404);
+        assert_eq!(call(port, "GET", "/nowhere", TOKEN, "").0, 404);
+        assert_eq!(call(port, "POST", "/state", TOKEN, "{not json").0, 400);
+        let (status, body) = call(port, "POST", "/state", TOKEN, r#"{"colour": "red"}"#);
+        assert_eq!(status, 400);
+        assert!(body.contains("colour"), "{}", body);
+        assert_eq!(call(port, "POST", "/execute", TOKEN, "[1, 2]").0, 400);
+        assert!(api.take_commands().is_empty());
+    }
+
+    #[test]
+    fn state_updates_are_queued_and_status_is_published() {
+        let (api, _) = start();
+        let (status, body) = call(api.port, "POST", "/state", TOKEN, r#"{"prompt": "hello"}"#);
+        assert_eq!((status, body.contains("queued")), (202, true), "{}", body);
+        let ApiCommand::SetState(update) = next_command(&api) else {
+            panic!("expected a state update");
+        };
+        assert_eq!(update.prompt.as_deref(), Some("hello"));
+
+        api.publish_status(ApiStatus {
+            prompt: "hello".to_string(),
+            ..Default::default()
+        });
+        let (status, body) = call(api.port, "GET", "/status", TOKEN, "");
+        assert_eq!(status, 200);
+        let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(status["prompt"], "hello");
+    }
+
+    #[test]
+    fn execute_runs_gia_and_publishes_the_result() {
+        let (api, is_executing) = start();
+        let (_dir, program) = fake_gia();
+        let client = call_async(
+            api.port,
+            "POST",
+            "/execute",
+            r#"{"values": {"lang": "Rust"}}"#,
+        );
+
+        // What the window does with the request
+        let ApiCommand::Execute { values, reply, .. } = next_command(&api) else {
+            panic!("expected an execute command");
+        };
+        *is_executing.lock().unwrap() = true;
+        reply.send(Ok(())).unwrap();
+        let (status, body) = client.join().unwrap();
+        assert_eq!(status, 202);
+        assert!(body.contains("started"), "{}", body);
+
+        // Busy until gia finished
+        assert_eq!(call(api.port, "POST", "/execute", TOKEN, "").0, 409);
+        let result = gia::run(GiaRequest {
+            program,
+            args: vec![values["lang"].clone()],
+            ..Default::default()
+        });
+        api.publish_result(&result);
+        *is_executing.lock().unwrap() = false;
+
+        let (status, body) = call(api.port, "GET", "/result", TOKEN, "");
+        assert_eq!(status, 200);
+        assert!(body.contains("fake gia: Rust"), "{}", body);
+    }
+
+    #[test]
+    fn execute_reports_what_the_window_did() {
+        let (api, _) = start();
+
+        // Refused, e.g. for a placeholder without a value
+        let client = call_async(api.port, "POST", "/execute", "");
+        let ApiCommand::Execute { reply, .. } = next_command(&api) else {
+            panic!("expected an execute command");
+        };
+        reply
+            .send(Err("no value for template placeholders: lang".to_string()))
+            .unwrap();
+        let (status, body) = client.join().unwrap();
+        assert_eq!(status, 409);
+        assert!(body.contains("lang"), "{}", body);
+
+        // Dropped without an answer
+        let client = call_async(api.port, "POST", "/execute", "");
+        drop(next_command(&api));
+        assert_eq!(client.join().unwrap().0, 500);
+
+        // Not picked up in time, e.g. while the window is minimized
+        let client = call_async(api.port, "POST", "/execute", "");
+        let (status, body) = client.join().unwrap();
+        assert_eq!(status, 202);
+        assert!(body.contains("queued"), "{}", body);
+        assert!(matches!(next_command(&api), ApiCommand::Execute { .. }));
+    }
+}
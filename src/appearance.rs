@@ -0,0 +1,144 @@
+//! Theme, fonts and zoom.
+
+use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::{Appearance, Theme};
+
+/// Fonts bundled with egui that can be chosen by name. Emoji are always
+/// covered by the bundled Noto Emoji and emoji icon fonts.
+pub const BUILTIN_FONTS: &[&str] = &["Ubuntu-Light", "Hack"];
+
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 3.0;
+
+/// Installed fonts with CJK coverage, in order of preference.
+fn cjk_candidates() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        let fonts = PathBuf::from(std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".into()))
+            .join("Fonts");
+        [
+            "msyh.ttc",
+            "YuGothM.ttc",
+            "msgothic.ttc",
+            "malgun.ttf",
+            "simsun.ttc",
+        ]
+        .iter()
+        .map(|name| fonts.join(name))
+        .collect()
+    } else if cfg!(target_os = "macos") {
+        [
+            "/System/Library/Fonts/PingFang.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+            "/Library/Fonts/Arial Unicode.ttf",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    } else {
+        [
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+            "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+}
+
+/// Reads a font file, checking it parses since egui panics on invalid fonts.
+fn read_font(path: &Path) -> Result<FontData, String> {
+    let bytes =
+        fs::read(path).map_err(|e| format!("Failed to read font {}: {}", path.display(), e))?;
+    ab_glyph::FontRef::try_from_slice(&bytes)
+        .map_err(|e| format!("Not a font file {}: {}", path.display(), e))?;
+    Ok(FontData::from_owned(bytes))
+}
+
+/// Puts `choice` (a built-in name or a font file) first in `family`.
+fn set_primary(
+    fonts: &mut FontDefinitions,
+    family: FontFamily,
+    choice: &str,
+) -> Result<(), String> {
+    let choice = choice.trim();
+    if choice.is_empty() {
+        return Ok(());
+    }
+    let name = if BUILTIN_FONTS.contains(&choice) {
+        choice.to_string()
+    } else {
+        let font = read_font(Path::new(choice))?;
+        let name = format!("{:?}: {}", family, choice);
+        fonts.font_data.insert(name.clone(), Arc::new(font));
+        name
+    };
+    let names = fonts.families.entry(family).or_default();
+    names.retain(|existing| *existing != name);
+    names.insert(0, name);
+    Ok(())
+}
+
+/// egui's bundled fonts with the chosen fonts first and an installed CJK font
+/// as the last fallback.
+pub fn fonts(appearance: &Appearance) -> (FontDefinitions, Vec<String>) {
+    let mut fonts = FontDefinitions::default();
+    let mut errors = Vec::new();
+
+    for (family, choice) in [
+        (FontFamily::Proportional, &appearance.proportional_font),
+        (FontFamily::Monospace, &appearance.monospace_font),
+    ] {
+        if let Err(e) = set_primary(&mut fonts, family, choice) {
+            errors.push(e);
+        }
+    }
+
+    if appearance.cjk_fallback
+        && let Some(font) = cjk_candidates()
+            .iter()
+            .find_map(|path| read_font(path).ok())
+    {
+        fonts.font_data.insert("cjk".to_string(), Arc::new(font));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts
+                .families
+                .entry(family)
+                .or_default()
+                .push("cjk".to_string());
+        }
+    }
+    (fonts, errors)
+}
+
+pub fn apply_theme(ctx: &egui::Context, theme: Theme) {
+    ctx.set_theme(match theme {
+        Theme::System => egui::ThemePreference::System,
+        Theme::Dark => egui::ThemePreference::Dark,
+        Theme::Light => egui::ThemePreference::Light,
+    });
+}
+
+pub fn clamp_zoom(zoom: f32) -> f32 {
+    ((zoom * 10.0).round() / 10.0).clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// Applies theme, fonts and zoom; returns the fonts that could not be loaded.
+pub fn apply(ctx: &egui::Context, appearance: &Appearance) -> Vec<String> {
+    // Zoom keys are the Zoom in/out and Reset zoom commands, so unbinding them
+    // turns keyboard zoom off
+    ctx.options_mut(|options| options.zoom_with_keyboard = false);
+    apply_theme(ctx, appearance.theme);
+    ctx.set_zoom_factor(clamp_zoom(appearance.zoom));
+    let (fonts, errors) = fonts(appearance);
+    ctx.set_fonts(fonts);
+    errors
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: giagui [OPTIONS] [PROMPT]...
+
+Arguments:
+  [PROMPT]...              Prompt text (words are joined with spaces)
+
+Options:
+      --model <MODEL>      Model to select
+      --task <TASK>        Task to select (repeatable, combined in order)
+      --role <ROLE>        Role to select (repeatable, combined in order)
+      --attach <PATH>      Attach a file or directory (repeatable)
+      --options-file <PATH>
+                           Read additional gia options from a file, one per line
+      --preset <NAME>      Apply a saved preset before the other options
+      --send               Send the prompt immediately
+      --new-instance       Open a new window instead of forwarding to a running one
+      --api-port <PORT>    Enable the localhost HTTP control API on this port
+  -h, --help               Print this help
+
+When stdin is piped, its content is appended to the prompt.
+The API token is read from GIAGUI_API_TOKEN or generated into the config directory.";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CliArgs {
+    pub prompt: Option<String>,
+    pub model: Option<String>,
+    pub tasks: Vec<String>,
+    pub roles: Vec<String>,
+    pub attach: Vec<PathBuf>,
+    pub options_file: Option<PathBuf>,
+    pub preset: Option<String>,
+    pub send: bool,
+    #[serde(skip)]
+    pub new_instance: bool,
+    #[serde(skip)]
+    pub api_port: Option<u16>,
+    #[serde(skip)]
+    pub help: bool,
+}
+
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut words = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            words.extend(args.by_ref());
+            break;
+        }
+        if arg == "-h" || arg == "--help" {
+            parsed.help = true;
+            continue;
+        }
+        if arg == "--send" {
+            parsed.send = true;
+            continue;
+        }
+        if arg == "--new-instance" {
+            parsed.new_instance = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            words.push(arg);
+            continue;
+        }
+
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match name.as_str() {
+            "--model" => parsed.model = Some(value()?),
+            "--task" => parsed.tasks.push(value()?),
+            "--role" => parsed.roles.push(value()?),
+            "--attach" => parsed.attach.push(PathBuf::from(value()?)),
+            "--options-file" => parsed.options_file = Some(PathBuf::from(value()?)),
+            "--preset" => parsed.preset = Some(value()?),
+            "--api-port" => {
+                let port = value()?;
+                parsed.api_port = Some(
+                    port.parse()
+                        .map_err(|_| format!("Invalid port: {}", port))?,
+                );
+            }
+            _ => return Err(format!("Unknown option: {}", name)),
+        }
+    }
+
+    if !words.is_empty() {
+        parsed.prompt = Some(words.join(" "));
+    }
+
+    Ok(parsed)
+}
+
+/// Reads the prompt from stdin when it is piped rather than a terminal.
+pub fn read_piped_stdin() -> Option<String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+
+    let mut content = String::new();
+    stdin.read_to_string(&mut content).ok()?;
+    let content = content.trim_end().to_string();
+    (!content.is_empty()).then_some(content)
+}
+
+impl CliArgs {
+    /// Resolves relative paths so another process can use them.
+    pub fn make_paths_absolute(&mut self) {
+        let Ok(current_dir) = std::env::current_dir() else {
+            return;
+        };
+        for path in &mut self.attach {
+            *path = current_dir.join(&*path);
+        }
+        if let Some(path) = &mut self.options_file {
+            *path = current_dir.join(&*path);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Registry of the actions reachable from buttons, shortcuts and the command palette.
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    Send,
+    Record,
+    Clear,
+    Copy,
+    Export,
+    ShowConversation,
+    Help,
+    ToggleClipboard,
+    ToggleBrowserOutput,
+    ToggleResume,
+    ToggleTts,
+    SearchTasks,
+    SearchRoles,
+    Regenerate,
+    PreviousVariant,
+    NextVariant,
+    Diff,
+    EditPrompt,
+    EditResponse,
+    Compare,
+    NewConversation,
+    Conversations,
+    Presets,
+    ManageLibrary,
+    TogglePreview,
+    Palette,
+    Settings,
+    Shortcuts,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    /// Cycles through system, dark and light.
+    SwitchTheme,
+    /// Applies the n-th preset (1-based).
+    Preset(u8),
+}
+
+/// Where a shortcut applies. A binding for the focused field shadows a global
+/// one with the same keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Scope {
+    #[default]
+    Global,
+    /// Only while the prompt editor has focus.
+    Prompt,
+    /// Only while the response has focus.
+    Response,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::Global, Scope::Prompt, Scope::Response];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::Global => "Everywhere",
+            Scope::Prompt => "Prompt editor",
+            Scope::Response => "Response",
+        }
+    }
+
+    /// Name used in the config file, e.g. `prompt: Ctrl+Enter`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Scope::Global => "global",
+            Scope::Prompt => "prompt",
+            Scope::Response => "response",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Scope> {
+        Scope::ALL.into_iter().find(|scope| scope.id() == id)
+    }
+}
+
+impl Command {
+    pub const ALL: &[Command] = &[
+        Command::Send,
+        Command::Record,
+        Command::Clear,
+        Command::Copy,
+        Command::Export,
+        Command::ShowConversation,
+        Command::Help,
+        Command::ToggleClipboard,
+        Command::ToggleBrowserOutput,
+        Command::ToggleResume,
+        Command::ToggleTts,
+        Command::SearchTasks,
+        Command::SearchRoles,
+        Command::Regenerate,
+        Command::PreviousVariant,
+        Command::NextVariant,
+        Command::Diff,
+        Command::EditPrompt,
+        Command::EditResponse,
+        Command::Compare,
+        Command::NewConversation,
+        Command::Conversations,
+        Command::Presets,
+        Command::ManageLibrary,
+        Command::TogglePreview,
+        Command::Palette,
+        Command::Settings,
+        Command::Shortcuts,
+        Command::ZoomIn,
+        Command::ZoomOut,
+        Command::ResetZoom,
+        Command::SwitchTheme,
+        Command::Preset(1),
+        Command::Preset(2),
+        Command::Preset(3),
+        Command::Preset(4),
+        Command::Preset(5),
+        Command::Preset(6),
+        Command::Preset(7),
+        Command::Preset(8),
+        Command::Preset(9),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Command::Send => "Send prompt",
+            Command::Record => "Send with audio recording",
+            Command::Clear => "Clear form",
+            Command::Copy => "Copy response to clipboard",
+            Command::Export => "Export response or session",
+            Command::ShowConversation => "Show conversation in browser",
+            Command::Help => "Show gia help",
+            Command::ToggleClipboard => "Toggle clipboard input (-c)",
+            Command::ToggleBrowserOutput => "Toggle browser output",
+            Command::ToggleResume => "Toggle resume conversation (-R)",
+            Command::ToggleTts => "Toggle text-to-speech",
+            Command::SearchTasks => "Search tasks",
+            Command::SearchRoles => "Search roles",
+            Command::Regenerate => "Regenerate response",
+            Command::PreviousVariant => "Previous response variant",
+            Command::NextVariant => "Next response variant",
+            Command::Diff => "Diff responses",
+            Command::EditPrompt => "Edit prompt in external editor",
+            Command::EditResponse => "Edit response in external editor",
+            Command::Compare => "Compare models",
+            Command::NewConversation => "New conversation",
+            Command::Conversations => "Manage conversations",
+            Command::Presets => "Manage presets",
+            Command::ManageLibrary => "Manage tasks and roles",
+            Command::TogglePreview => "Toggle task/role preview",
+            Command::Palette => "Command palette",
+            Command::Settings => "Settings",
+            Command::Shortcuts => "Keyboard shortcuts",
+            Command::ZoomIn => "Zoom in",
+            Command::ZoomOut => "Zoom out",
+            Command::ResetZoom => "Reset zoom",
+            Command::SwitchTheme => "Switch theme (system, dark, light)",
+            Command::Preset(n) => return format!("Apply preset {}", n),
+        }
+        .to_string()
+    }
+
+    /// Stable name used for the command in the config file.
+    pub fn id(self) -> String {
+        match self {
+            Command::Send => "send",
+            Command::Record => "record",
+            Command::Clear => "clear",
+            Command::Copy => "copy",
+            Command::Export => "export",
+            Command::ShowConversation => "show_conversation",
+            Command::Help => "help",
+            Command::ToggleClipboard => "toggle_clipboard",
+            Command::ToggleBrowserOutput => "toggle_browser_output",
+            Command::ToggleResume => "toggle_resume",
+            Command::ToggleTts => "toggle_tts",
+            Command::SearchTasks => "search_tasks",
+            Command::SearchRoles => "search_roles",
+            Command::Regenerate => "regenerate",
+            Command::PreviousVariant => "previous_variant",
+            Command::NextVariant => "next_variant",
+            Command::Diff => "diff",
+            Command::EditPrompt => "edit_prompt",
+            Command::EditResponse => "edit_response",
+            Command::Compare => "compare",
+            Command::NewConversation => "new_conversation",
+            Command::Conversations => "conversations",
+            Command::Presets => "presets",
+            Command::ManageLibrary => "manage_library",
+            Command::TogglePreview => "toggle_preview",
+            Command::Palette => "palette",
+            Command::Settings => "settings",
+            Command::Shortcuts => "shortcuts",
+            Command::ZoomIn => "zoom_in",
+            Command::ZoomOut => "zoom_out",
+            Command::ResetZoom => "reset_zoom",
+            Command::SwitchTheme => "switch_theme",
+            Command::Preset(n) => return format!("preset_{}", n),
+        }
+        .to_string()
+    }
+
+    pub fn from_id(id: &str) -> Option<Command> {
+        Command::ALL
+            .iter()
+            .copied()
+            .find(|command| command.id() == id)
+    }
+
+    pub fn default_shortcut(self) -> Option<KeyboardShortcut> {
+        let ctrl = Modifiers::CTRL;
+        let ctrl_shift = Modifiers::CTRL | Modifiers::SHIFT;
+        let (modifiers, key) = match self {
+            Command::Send => (ctrl, Key::Enter),
+            Command::Record => (ctrl, Key::R),
+            Command::Clear => (ctrl, Key::L),
+            Command::Copy => (ctrl_shift, Key::C),
+            Command::Export => (ctrl, Key::S),
+            Command::ShowConversation => (ctrl, Key::O),
+            Command::Help => (Modifiers::NONE, Key::F1),
+            Command::ToggleClipboard => (ctrl, Key::Num1),
+            Command::ToggleBrowserOutput => (ctrl, Key::Num2),
+            Command::ToggleResume => (ctrl, Key::Num3),
+            Command::ToggleTts => (ctrl, Key::Num4),
+            Command::SearchTasks => (ctrl, Key::T),
+            Command::SearchRoles => (ctrl_shift, Key::T),
+            Command::Regenerate => (ctrl_shift, Key::R),
+            Command::PreviousVariant => (Modifiers::ALT, Key::ArrowLeft),
+            Command::NextVariant => (Modifiers::ALT, Key::ArrowRight),
+            Command::Diff => (ctrl, Key::D),
+            Command::EditPrompt => (ctrl, Key::E),
+            Command::EditResponse => (ctrl_shift, Key::E),
+            Command::Palette => (ctrl_shift, Key::P),
+            Command::Settings => (ctrl, Key::Comma),
+            Command::ZoomIn => (ctrl, Key::Plus),
+            Command::ZoomOut => (ctrl, Key::Minus),
+            Command::ResetZoom => (ctrl, Key::Num0),
+            Command::Preset(n) => (Modifiers::ALT, Key::from_name(&n.to_string())?),
+            Command::Compare
+            | Command::NewConversation
+            | Command::Conversations
+            | Command::Presets
+            | Command::ManageLibrary
+            | Command::TogglePreview
+            | Command::Shortcuts
+            | Command::SwitchTheme => return None,
+        };
+        Some(KeyboardShortcut::new(modifiers, key))
+    }
+
+    /// Where the default shortcut applies. Alt+Left/Right move by word while
+    /// typing, so the variant keys only act on the response.
+    pub fn default_scope(self) -> Scope {
+        match self {
+            Command::PreviousVariant | Command::NextVariant => Scope::Response,
+            _ => Scope::Global,
+        }
+    }
+}
+
+/// `shortcut` as text, e.g. `Ctrl+Shift+P`.
+pub fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+    shortcut.format(&egui::ModifierNames::NAMES, cfg!(target_os = "macos"))
+}
+
+/// Whether a key press triggers `shortcut`. Plus needs Shift on most layouts
+/// and arrives as Equals without it, so both count, as in egui's own zoom keys.
+fn matches(key: Key, modifiers: Modifiers, shortcut: &KeyboardShortcut) -> bool {
+    if shortcut.logical_key == Key::Plus && matches!(key, Key::Plus | Key::Equals) {
+        return modifiers.matches_exact(shortcut.modifiers)
+            || modifiers.matches_exact(shortcut.modifiers | Modifiers::SHIFT);
+    }
+    key == shortcut.logical_key && modifiers.matches_exact(shortcut.modifiers)
+}
+
+/// Whether `shortcut` was pressed this frame with exactly its modifiers. The key
+/// events are removed so the focused widget does not see them too (e.g. a
+/// newline for Ctrl+Enter).
+pub fn consume(input: &mut egui::InputState, shortcut: &KeyboardShortcut) -> bool {
+    let mut pressed = false;
+    input.events.retain(|event| {
+        let is_match = matches!(
+            event,
+            egui::Event::Key { key, modifiers, pressed: true, .. }
+                if matches(*key, *modifiers, shortcut)
+        );
+        pressed |= is_match;
+        !is_match
+    });
+    pressed
+}
+
+/// What a command palette entry does.
+pub enum PaletteEntry {
+    Command(Command),
+    Model(String),
+    Task(String),
+    Role(String),
+    /// Index into the presets.
+    Preset(usize),
+    /// Index into the post-processing actions.
+    Action(usize),
+}
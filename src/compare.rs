@@ -0,0 +1,140 @@
+//! Running one request against several models side by side.
+
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::config::Model;
+use crate::diff;
+use crate::gia::{self, GiaRequest, GiaResult};
+
+/// Results of one comparison, in the order the models were selected.
+type Results = Arc<Mutex<Vec<Option<GiaResult>>>>;
+
+#[derive(Default)]
+pub struct Compare {
+    pub open: bool,
+    /// Models to run the next comparison with.
+    pub models: Vec<String>,
+    /// Models of the last comparison, matching `results`.
+    running: Vec<String>,
+    results: Results,
+    show_diff: bool,
+    /// Word diffs of each column against the first.
+    diffs: diff::Cache,
+}
+
+impl Compare {
+    pub fn is_running(&self) -> bool {
+        self.results.lock().unwrap().iter().any(Option::is_none)
+    }
+
+    /// Runs every request in its own thread.
+    pub fn start(&mut self, requests: Vec<GiaRequest>) {
+        self.running = requests
+            .iter()
+            .map(|request| request.model.clone())
+            .collect();
+        self.results = Arc::new(Mutex::new(vec![None; requests.len()]));
+        self.diffs.clear();
+
+        for (index, request) in requests.into_iter().enumerate() {
+            let results = Arc::clone(&self.results);
+            thread::spawn(move || {
+                let result = gia::run(request);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    }
+
+    /// Shows the compare window. Returns true when a new run was requested.
+    pub fn show(&mut self, ctx: &egui::Context, models: &[Model]) -> bool {
+        if !self.open {
+            return false;
+        }
+
+        let running = self.is_running();
+        if running {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+
+        let mut open = self.open;
+        let mut run = false;
+        egui::Window::new("Compare models")
+            .open(&mut open)
+            .default_size([900.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for model in models {
+                        let mut selected = self.models.contains(&model.name);
+                        if ui.checkbox(&mut selected, &model.label).changed() {
+                            if selected {
+                                self.models.push(model.name.clone());
+                            } else {
+                                self.models.retain(|m| *m != model.name);
+                            }
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let enabled = self.models.len() >= 2 && !running;
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Run comparison"))
+                        .on_disabled_hover_text("Select at least two models")
+                        .clicked()
+                    {
+                        run = true;
+                    }
+                    ui.checkbox(&mut self.show_diff, "Word diff against first column");
+                    if running {
+                        ui.spinner();
+                    }
+                });
+                ui.separator();
+
+                let results = self.results.lock().unwrap().clone();
+                if results.is_empty() {
+                    ui.weak("Runs the current prompt, task, role and attachments without -R.");
+                    return;
+                }
+                let baseline = results[0].as_ref().map(GiaResult::response_text);
+
+                ui.columns(results.len(), |columns| {
+                    for (index, (ui, result)) in columns.iter_mut().zip(&results).enumerate() {
+                        ui.strong(&self.running[index]);
+                        let Some(result) = result else {
+                            ui.spinner();
+                            continue;
+                        };
+                        let text = result.response_text();
+                        ui.weak(format!(
+                            "{:.1} s · {} words · {} chars{}",
+                            result.duration_ms as f64 / 1000.0,
+                            text.split_whitespace().count(),
+                            text.chars().count(),
+                            match result.exit_code {
+                                Some(0) => String::new(),
+                                Some(code) => format!(" · exit {}", code),
+                                None => " · failed".to_string(),
+                            }
+                        ));
+                        egui::ScrollArea::vertical()
+                            .id_salt(("compare_column", index))
+                            .show(ui, |ui| match &baseline {
+                                Some(baseline) if self.show_diff && index > 0 => {
+                                    let diff =
+                                        self.diffs.get(baseline, &text, diff::Granularity::Word);
+                                    ui.label(diff.unified(ui));
+                                }
+                                _ => {
+                                    ui.monospace(text);
+                                }
+                            });
+                    }
+                });
+            });
+
+        self.open = open;
+        run
+    }
+}
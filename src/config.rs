@@ -0,0 +1,396 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::export::ExportSettings;
+use crate::keymap::Overrides;
+use crate::postprocess::Action;
+
+/// Directory holding giagui's own files (presets, settings, ...).
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("giagui"))
+}
+
+/// Which parts of the form are reset after a prompt was sent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResetPolicy {
+    pub clear_task: bool,
+    pub clear_role: bool,
+    pub clear_clipboard: bool,
+    /// Turn on `-R` so the next prompt continues the conversation.
+    pub enable_resume: bool,
+    pub clear_prompt: bool,
+    /// Remove the `-f`/`-i` attachment lines from the options.
+    pub clear_attachments: bool,
+    pub clear_options: bool,
+}
+
+impl Default for ResetPolicy {
+    fn default() -> Self {
+        Self {
+            clear_task: true,
+            clear_role: true,
+            clear_clipboard: true,
+            enable_resume: true,
+            clear_prompt: false,
+            clear_attachments: false,
+            clear_options: false,
+        }
+    }
+}
+
+impl ResetPolicy {
+    /// Short description of what happens after sending.
+    pub fn summary(&self) -> String {
+        let cleared: Vec<&str> = [
+            (self.clear_prompt, "prompt"),
+            (self.clear_task, "task"),
+            (self.clear_role, "role"),
+            (self.clear_clipboard, "clipboard"),
+            (self.clear_options, "options"),
+            (self.clear_attachments && !self.clear_options, "attachments"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+
+        let mut summary = if cleared.is_empty() {
+            "After send: nothing resets".to_string()
+        } else {
+            format!("After send: clears {}", cleared.join(", "))
+        };
+        if self.enable_resume {
+            summary.push_str("; resume on");
+        }
+        summary
+    }
+}
+
+/// State of the form when giagui starts.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct General {
+    pub model: String,
+    pub tts_language: String,
+    /// Languages offered for text-to-speech.
+    pub tts_languages: Vec<String>,
+    pub use_clipboard: bool,
+    pub browser_output: bool,
+    pub resume: bool,
+    pub tts_enabled: bool,
+}
+
+impl Default for General {
+    fn default() -> Self {
+        Self {
+            model: "gemini-2.5-flash-lite".to_string(),
+            tts_language: "de-DE".to_string(),
+            tts_languages: vec!["de-DE".to_string(), "en-US".to_string()],
+            use_clipboard: false,
+            browser_output: false,
+            resume: false,
+            tts_enabled: false,
+        }
+    }
+}
+
+/// How gia is started.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GiaSettings {
+    pub executable: String,
+    /// Passed before the options of every request.
+    pub extra_args: Vec<String>,
+    /// Used when no workspace sets one; empty runs gia in giagui's directory.
+    pub working_dir: String,
+}
+
+impl GiaSettings {
+    /// The executable to run, `gia` unless configured.
+    pub fn program(&self) -> &str {
+        match self.executable.trim() {
+            "" => "gia",
+            executable => executable,
+        }
+    }
+}
+
+impl Default for GiaSettings {
+    fn default() -> Self {
+        Self {
+            executable: "gia".to_string(),
+            extra_args: Vec::new(),
+            working_dir: String::new(),
+        }
+    }
+}
+
+/// A model offered in the selectors.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Model {
+    /// Name passed to `gia --model`.
+    pub name: String,
+    /// Shown in the selectors; the name when left out.
+    #[serde(default)]
+    pub label: String,
+}
+
+impl Model {
+    fn new(name: &str, label: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            label: label.to_string(),
+        }
+    }
+}
+
+pub fn default_models() -> Vec<Model> {
+    vec![
+        Model::new("gemini-2.5-pro", "Gemini 2.5 Pro"),
+        Model::new("gemini-2.5-flash", "Gemini 2.5 Flash"),
+        Model::new("gemini-2.5-flash-lite", "Gemini 2.5 Flash-Lite"),
+        Model::new("gemini-2.0-flash", "Gemini 2.0 Flash"),
+        Model::new("gemini-2.0-flash-lite", "Gemini 2.0 Flash-Lite"),
+    ]
+}
+
+/// How dropped or passed files become `-f`/`-i` options.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Attachments {
+    /// Extensions attached as media with `-i`; everything else uses `-f`.
+    pub media_extensions: Vec<String>,
+    /// Attach the files of dropped folders, including subfolders.
+    pub recurse_directories: bool,
+    /// Skip files and folders starting with a dot (`.git`, ...).
+    pub skip_hidden: bool,
+    /// Most files attached from one folder; 0 means no limit.
+    pub max_files: usize,
+}
+
+impl Default for Attachments {
+    fn default() -> Self {
+        Self {
+            media_extensions: [
+                "jpg", "jpeg", "png", "webp", "heic", "pdf", "ogg", "opus", "mp3", "m4a", "mp4",
+            ]
+            .map(String::from)
+            .to_vec(),
+            recurse_directories: true,
+            skip_hidden: false,
+            max_files: 0,
+        }
+    }
+}
+
+impl Attachments {
+    pub fn is_media(&self, path: &std::path::Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                self.media_extensions
+                    .iter()
+                    .any(|media| media.eq_ignore_ascii_case(ext))
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follows the operating system.
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::System => "System",
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    pub theme: Theme,
+    /// Built-in font name or path to a font file; empty uses the default.
+    pub proportional_font: String,
+    pub monospace_font: String,
+    /// Add an installed CJK font for characters the chosen fonts lack.
+    pub cjk_fallback: bool,
+    pub zoom: f32,
+    /// Show the task/role preview panel at startup.
+    pub show_preview: bool,
+    /// Height of the prompt editor in lines.
+    pub prompt_rows: usize,
+    pub response_monospace: bool,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            theme: Theme::System,
+            proportional_font: String::new(),
+            monospace_font: String::new(),
+            cjk_fallback: true,
+            zoom: 1.0,
+            show_preview: false,
+            prompt_rows: 3,
+            response_monospace: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    /// Responses kept for regenerating, diffing and exporting; 0 keeps all.
+    pub max_turns: usize,
+    /// Conversations listed in the conversations window; 0 lists all.
+    pub max_conversations: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Privacy {
+    /// Store the values entered for template placeholders on disk.
+    pub remember_template_values: bool,
+    /// Show the start of the first message for unnamed conversations.
+    pub show_conversation_previews: bool,
+}
+
+impl Default for Privacy {
+    fn default() -> Self {
+        Self {
+            remember_template_values: true,
+            show_conversation_previews: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub general: General,
+    pub gia: GiaSettings,
+    #[serde(rename = "model")]
+    pub models: Vec<Model>,
+    pub attachments: Attachments,
+    pub appearance: Appearance,
+    pub history: HistorySettings,
+    pub privacy: Privacy,
+    pub reset: ResetPolicy,
+    pub export: ExportSettings,
+    /// Command to edit the prompt or response with; empty uses `$VISUAL`/`$EDITOR`.
+    pub editor: String,
+    #[serde(rename = "action")]
+    pub actions: Vec<Action>,
+    /// Rebound commands by id, e.g. `clear = "Ctrl+Shift+L"`; `""` unbinds.
+    pub shortcuts: Overrides,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            general: General::default(),
+            gia: GiaSettings::default(),
+            models: default_models(),
+            attachments: Attachments::default(),
+            appearance: Appearance::default(),
+            history: HistorySettings::default(),
+            privacy: Privacy::default(),
+            reset: ResetPolicy::default(),
+            export: ExportSettings::default(),
+            editor: String::new(),
+            actions: Vec::new(),
+            shortcuts: Overrides::new(),
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("settings.toml"))
+}
+
+/// Where a settings file that failed to parse is kept before it is replaced.
+fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("toml.bak")
+}
+
+fn parse_settings(content: &str) -> Result<Settings, String> {
+    let mut settings: Settings = toml::from_str(content).map_err(|e| e.to_string())?;
+    for model in &mut settings.models {
+        if model.label.trim().is_empty() {
+            model.label = model.name.clone();
+        }
+    }
+    for action in &mut settings.actions {
+        if action.name.trim().is_empty() {
+            action.name = action.command.clone();
+        }
+    }
+    Ok(settings)
+}
+
+/// The saved settings, or the defaults together with the reason the file
+/// could not be used.
+pub fn load_settings() -> (Settings, Option<String>) {
+    let Some(path) = settings_path() else {
+        return (Settings::default(), None);
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Settings::default(), None),
+        Err(e) => {
+            let error = format!("Failed to read {}: {}; using defaults", path.display(), e);
+            return (Settings::default(), Some(error));
+        }
+    };
+    match parse_settings(&content) {
+        Ok(settings) => (settings, None),
+        Err(e) => {
+            let error = format!(
+                "Failed to parse {}: {}; using defaults, the file is backed up to {} before settings are saved",
+                path.display(),
+                e.lines().next().unwrap_or_default(),
+                backup_path(&path).display()
+            );
+            (Settings::default(), Some(error))
+        }
+    }
+}
+
+/// Writes `settings`. A file that does not parse is backed up first rather
+/// than overwritten, so hand edits with a typo are not lost.
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    let path = settings_path().ok_or("No config directory available")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    if let Ok(existing) = fs::read_to_string(&path)
+        && parse_settings(&existing).is_err()
+    {
+        let backup = backup_path(&path);
+        fs::copy(&path, &backup).map_err(|e| {
+            format!(
+                "Not saving settings: failed to back up {} to {}: {}",
+                path.display(),
+                backup.display(),
+                e
+            )
+        })?;
+    }
+    let content = toml::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,202 @@
+//! gia's stored conversations (`~/.gia/conversations/<id>.json`) and the names
+//! given to them in giagui.
+//!
+//! Resuming a specific conversation needs a gia with `--resume=<id>`; [`check`]
+//! verifies that and the storage format before the browser is offered.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::SystemTime;
+
+use crate::config::config_dir;
+
+pub struct Conversation {
+    pub id: String,
+    pub modified: SystemTime,
+    /// Start of the first message, to recognise unnamed conversations.
+    pub preview: String,
+}
+
+fn conversations_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".gia").join("conversations"))
+}
+
+/// A stored conversation as it was before a turn was sent.
+pub struct Snapshot {
+    id: String,
+    content: String,
+}
+
+/// Copies conversation `id`, if gia has stored it.
+pub fn snapshot(id: &str) -> Option<Snapshot> {
+    let path = conversations_dir()?.join(format!("{}.json", id));
+    Some(Snapshot {
+        id: id.to_string(),
+        content: fs::read_to_string(path).ok()?,
+    })
+}
+
+/// Stores `snapshot` as a new conversation and returns its id, so a request
+/// can be sent against that state again without touching the original.
+pub fn branch(snapshot: &Snapshot) -> Result<String, String> {
+    let dir = conversations_dir().ok_or("No home directory available")?;
+    let millis = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let id = format!("{}-{}", snapshot.id, millis);
+    let path = dir.join(format!("{}.json", id));
+    fs::File::create_new(&path)
+        .and_then(|mut file| file.write_all(snapshot.content.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(id)
+}
+
+/// Whether the installed gia supports the conversation browser, or why not.
+pub fn check(program: &str) -> Result<(), String> {
+    let dir = conversations_dir().ok_or("No home directory available")?;
+    let entries: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|_| format!("gia keeps no conversations in {}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    let recognized = entries.iter().any(|path| {
+        path.extension().and_then(|ext| ext.to_str()) == Some("json")
+            && fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .is_some()
+    });
+    if !entries.is_empty() && !recognized {
+        return Err(format!(
+            "{} does not hold conversations in the expected <id>.json format",
+            dir.display()
+        ));
+    }
+
+    let help = Command::new(program)
+        .arg("--help")
+        .output()
+        .map_err(|e| format!("Failed to run {} --help: {}", program, e))?;
+    let help = String::from_utf8_lossy(&help.stdout) + String::from_utf8_lossy(&help.stderr);
+    if !help.contains("--resume") {
+        return Err(format!(
+            "{} has no --resume option to continue a specific conversation",
+            program
+        ));
+    }
+    Ok(())
+}
+
+/// All stored conversations, most recently used first.
+pub fn list() -> Vec<Conversation> {
+    let Some(entries) = conversations_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+
+    let mut conversations: Vec<Conversation> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.to_string();
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            let preview = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .and_then(|value| first_message(&value))
+                .map(|text| shorten(&text, 80))
+                .unwrap_or_default();
+            Some(Conversation {
+                id,
+                modified,
+                preview,
+            })
+        })
+        .collect();
+
+    conversations.sort_by_key(|conversation| std::cmp::Reverse(conversation.modified));
+    conversations
+}
+
+/// First message text in gia's conversation JSON, wherever it is nested.
+fn first_message(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Object(map) => {
+            for key in ["content", "text", "prompt"] {
+                if let Some(serde_json::Value::String(text)) = map.get(key)
+                    && !text.trim().is_empty()
+                {
+                    return Some(text.clone());
+                }
+            }
+            map.values().find_map(first_message)
+        }
+        serde_json::Value::Array(items) => items.iter().find_map(first_message),
+        _ => None,
+    }
+}
+
+fn shorten(text: &str, max_chars: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        line
+    } else {
+        format!("{}…", line.chars().take(max_chars).collect::<String>())
+    }
+}
+
+/// Human readable age such as `5 min ago`.
+pub fn age(modified: SystemTime) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(modified)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{} min ago", seconds / 60),
+        3_600..86_400 => format!("{} h ago", seconds / 3_600),
+        _ => format!("{} d ago", seconds / 86_400),
+    }
+}
+
+/// Names given to conversations, by conversation id.
+pub type Names = HashMap<String, String>;
+
+#[derive(Default, Serialize, Deserialize)]
+struct NamesFile {
+    #[serde(default)]
+    names: Names,
+}
+
+fn names_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("conversation-names.toml"))
+}
+
+pub fn load_names() -> Names {
+    names_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| toml::from_str::<NamesFile>(&content).ok())
+        .map(|file| file.names)
+        .unwrap_or_default()
+}
+
+pub fn save_names(names: &Names) -> Result<(), String> {
+    let path = names_path().ok_or("No config directory available")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = toml::to_string_pretty(&NamesFile {
+        names: names.clone(),
+    })
+    .map_err(|e| format!("Failed to serialize conversation names: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
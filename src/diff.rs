@@ -0,0 +1,261 @@
+//! Colourised diffs between two responses.
+
+use eframe::egui::{self, Color32, FontId, Stroke, TextFormat, text::LayoutJob};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+
+const INSERTED: Color32 = Color32::from_rgba_premultiplied(0, 90, 0, 70);
+const DELETED: Color32 = Color32::from_rgba_premultiplied(110, 0, 0, 70);
+
+#[derive(Clone, Copy, Default, PartialEq, Hash)]
+pub enum Granularity {
+    #[default]
+    Line,
+    Word,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Layout {
+    #[default]
+    Unified,
+    SideBySide,
+}
+
+struct Formats {
+    equal: TextFormat,
+    insert: TextFormat,
+    delete: TextFormat,
+}
+
+impl Formats {
+    fn new(ui: &egui::Ui) -> Self {
+        let font_id: FontId = egui::TextStyle::Monospace.resolve(ui.style());
+        let color = ui.visuals().text_color();
+        let equal = TextFormat::simple(font_id, color);
+        Self {
+            insert: TextFormat {
+                background: INSERTED,
+                ..equal.clone()
+            },
+            delete: TextFormat {
+                background: DELETED,
+                strikethrough: Stroke::new(1.0, color),
+                ..equal.clone()
+            },
+            equal,
+        }
+    }
+
+    fn get(&self, tag: ChangeTag) -> TextFormat {
+        match tag {
+            ChangeTag::Equal => self.equal.clone(),
+            ChangeTag::Insert => self.insert.clone(),
+            ChangeTag::Delete => self.delete.clone(),
+        }
+    }
+}
+
+/// The changes between two texts, grouped by diff operation. Computing them is
+/// the expensive part, so they are kept in a [`Cache`] rather than redone every
+/// frame.
+pub struct Diff {
+    granularity: Granularity,
+    ops: Vec<Vec<(ChangeTag, String)>>,
+}
+
+impl Diff {
+    pub fn new(old: &str, new: &str, granularity: Granularity) -> Self {
+        let diff = match granularity {
+            Granularity::Line => TextDiff::from_lines(old, new),
+            Granularity::Word => TextDiff::from_words(old, new),
+        };
+        let ops = diff
+            .ops()
+            .iter()
+            .map(|op| {
+                diff.iter_changes(op)
+                    .map(|change| (change.tag(), change.value().to_string()))
+                    .collect()
+            })
+            .collect();
+        Self { granularity, ops }
+    }
+
+    /// Changes from `old` to `new` in one text. Line diffs are prefixed with
+    /// `+`/`-`, word diffs highlight insertions green and strike deletions on red.
+    pub fn unified(&self, ui: &egui::Ui) -> LayoutJob {
+        let formats = Formats::new(ui);
+        let mut job = LayoutJob::default();
+
+        for (tag, value) in self.ops.iter().flatten() {
+            let format = formats.get(*tag);
+            match self.granularity {
+                Granularity::Line => {
+                    let mut line = format!("{}{}", tag, value);
+                    if !line.ends_with('\n') {
+                        line.push('\n');
+                    }
+                    job.append(&line, 0.0, format);
+                }
+                Granularity::Word => job.append(value, 0.0, format),
+            }
+        }
+        job
+    }
+
+    /// `old` with its deletions and `new` with its insertions highlighted. Line
+    /// diffs are padded so unchanged lines stay level.
+    pub fn side_by_side(&self, ui: &egui::Ui) -> (LayoutJob, LayoutJob) {
+        let formats = Formats::new(ui);
+        let (mut left, mut right) = (LayoutJob::default(), LayoutJob::default());
+
+        for changes in &self.ops {
+            let (mut left_lines, mut right_lines) = (0, 0);
+            for (tag, value) in changes {
+                let mut value = value.clone();
+                if self.granularity == Granularity::Line && !value.ends_with('\n') {
+                    value.push('\n');
+                }
+                if *tag != ChangeTag::Insert {
+                    left.append(&value, 0.0, formats.get(*tag));
+                    left_lines += 1;
+                }
+                if *tag != ChangeTag::Delete {
+                    right.append(&value, 0.0, formats.get(*tag));
+                    right_lines += 1;
+                }
+            }
+
+            if self.granularity == Granularity::Line {
+                for _ in left_lines..right_lines {
+                    left.append("\n", 0.0, formats.equal.clone());
+                }
+                for _ in right_lines..left_lines {
+                    right.append("\n", 0.0, formats.equal.clone());
+                }
+            }
+        }
+        (left, right)
+    }
+}
+
+/// Diffs computed so far, by the texts and granularity they were made from.
+#[derive(Default)]
+pub struct Cache {
+    diffs: HashMap<u64, Arc<Diff>>,
+}
+
+impl Cache {
+    /// Entries kept before the cache starts over.
+    const CAPACITY: usize = 32;
+
+    pub fn get(&mut self, old: &str, new: &str, granularity: Granularity) -> Arc<Diff> {
+        let mut hasher = DefaultHasher::new();
+        (old, new, granularity).hash(&mut hasher);
+        let key = hasher.finish();
+
+        if !self.diffs.contains_key(&key) && self.diffs.len() >= Self::CAPACITY {
+            self.diffs.clear();
+        }
+        Arc::clone(
+            self.diffs
+                .entry(key)
+                .or_insert_with(|| Arc::new(Diff::new(old, new, granularity))),
+        )
+    }
+
+    pub fn clear(&mut self) {
+        self.diffs.clear();
+    }
+}
+
+/// Window comparing two stored responses.
+#[derive(Default)]
+pub struct DiffView {
+    open: bool,
+    /// Indices into the entries passed to `show`.
+    from: usize,
+    to: usize,
+    granularity: Granularity,
+    layout: Layout,
+    cache: Cache,
+}
+
+impl DiffView {
+    /// Opens the view on the last two of `count` entries.
+    pub fn open(&mut self, count: usize) {
+        self.open = true;
+        self.to = count.saturating_sub(1);
+        self.from = count.saturating_sub(2);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows the window for `entries` as (label, response text).
+    pub fn show(&mut self, ctx: &egui::Context, entries: &[(String, String)]) {
+        if !self.open {
+            return;
+        }
+        if entries.is_empty() {
+            self.open = false;
+            return;
+        }
+        self.from = self.from.min(entries.len() - 1);
+        self.to = self.to.min(entries.len() - 1);
+
+        let mut open = self.open;
+        egui::Window::new("Response diff")
+            .open(&mut open)
+            .default_size([800.0, 500.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("diff_selection")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (label, index) in [("From", &mut self.from), ("To", &mut self.to)] {
+                            ui.label(label);
+                            egui::ComboBox::from_id_salt(("diff_entry", label))
+                                .width(420.0)
+                                .selected_text(&entries[*index].0)
+                                .show_ui(ui, |ui| {
+                                    for (position, (entry, _)) in entries.iter().enumerate() {
+                                        ui.selectable_value(index, position, entry);
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button("⇄ Swap").clicked() {
+                        std::mem::swap(&mut self.from, &mut self.to);
+                    }
+                    ui.separator();
+                    ui.radio_value(&mut self.granularity, Granularity::Line, "Lines");
+                    ui.radio_value(&mut self.granularity, Granularity::Word, "Words");
+                    ui.separator();
+                    ui.radio_value(&mut self.layout, Layout::Unified, "Unified");
+                    ui.radio_value(&mut self.layout, Layout::SideBySide, "Side by side");
+                });
+                ui.separator();
+
+                let (old, new) = (&entries[self.from].1, &entries[self.to].1);
+                let diff = self.cache.get(old, new, self.granularity);
+                egui::ScrollArea::vertical().show(ui, |ui| match self.layout {
+                    Layout::Unified => {
+                        ui.label(diff.unified(ui));
+                    }
+                    Layout::SideBySide => {
+                        let (left, right) = diff.side_by_side(ui);
+                        ui.columns(2, |columns| {
+                            columns[0].label(left);
+                            columns[1].label(right);
+                        });
+                    }
+                });
+            });
+        self.open = open;
+    }
+}
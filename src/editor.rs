@@ -0,0 +1,179 @@
+//! Editing the prompt or response in an external editor.
+
+use eframe::egui;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Prompt,
+    Response,
+}
+
+impl Target {
+    pub fn label(self) -> &'static str {
+        match self {
+            Target::Prompt => "prompt",
+            Target::Response => "response",
+        }
+    }
+}
+
+pub enum Event {
+    /// The file was saved with this content.
+    Changed(Target, String),
+    /// The editor was closed, or stopped being watched.
+    Closed(Target),
+    Failed(Target, String),
+}
+
+pub type Events = Arc<Mutex<Vec<Event>>>;
+
+/// Editors that return at once (e.g. a running GUI editor opening a new tab)
+/// are watched until this long after the last save, or until stopped.
+const DETACHED_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// The configured editor command, or `$VISUAL`/`$EDITOR`.
+pub fn command(configured: &str) -> Option<String> {
+    let from_env = || {
+        ["VISUAL", "EDITOR"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.trim().is_empty())
+    };
+    let command = Some(configured.trim().to_string())
+        .filter(|command| !command.is_empty())
+        .or_else(from_env);
+    if cfg!(windows) {
+        command.or_else(|| Some("notepad".to_string()))
+    } else {
+        command
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Splits the editor command like a shell would, so quoted paths and arguments
+/// with spaces work.
+fn split_command(command: &str) -> Result<Vec<String>, String> {
+    // Backslashes are path separators on Windows, not escapes
+    let command = if cfg!(windows) {
+        command.replace('\\', "\\\\")
+    } else {
+        command.to_string()
+    };
+    let parts = shell_words::split(&command)
+        .map_err(|e| format!("Invalid editor command '{}': {}", command, e))?;
+    if parts.is_empty() {
+        return Err("No editor command configured".to_string());
+    }
+    Ok(parts)
+}
+
+/// An open editor session; the file is watched until the editor exits, or
+/// until [`Session::stop`] for editors that detach.
+pub struct Session {
+    pub target: Target,
+    stop: Arc<AtomicBool>,
+}
+
+impl Session {
+    /// Stops watching after importing a last save.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Writes `text` to a new temp file, opens it with `command` and reports saves
+/// and the end of the session through `events`.
+pub fn open(
+    target: Target,
+    text: &str,
+    command: &str,
+    events: Events,
+    ctx: egui::Context,
+) -> Result<Session, String> {
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("giagui-{}-", target.label()))
+        .suffix(".md")
+        .tempfile()
+        .map_err(|e| format!("Failed to create a temp file: {}", e))?;
+    file.write_all(text.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", file.path().display(), e))?;
+    // Deleted when dropped at the end of the session
+    let path = file.into_temp_path();
+
+    let parts = split_command(command)?;
+    let mut child = Command::new(&parts[0])
+        .args(&parts[1..])
+        .arg(&*path)
+        .spawn()
+        .map_err(|e| format!("Failed to start '{}': {}", command, e))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let session = Session {
+        target,
+        stop: Arc::clone(&stop),
+    };
+
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut last_modified = modified(&path);
+        let mut last_activity = Instant::now();
+        let mut saved = false;
+        let mut exited = false;
+        let mut detached = false;
+
+        let send = |event| {
+            events.lock().unwrap().push(event);
+            ctx.request_repaint();
+        };
+
+        loop {
+            thread::sleep(Duration::from_millis(300));
+            let stopping = stop.load(Ordering::Relaxed);
+
+            // Checked before reading the file so a save right before exiting is seen
+            if !exited && !matches!(child.try_wait(), Ok(None)) {
+                exited = true;
+                // A quick exit without saving means the editor handed the file
+                // to an already running instance
+                detached = started.elapsed() < Duration::from_secs(2) && !saved;
+            }
+
+            let current = modified(&path);
+            if current != last_modified {
+                last_modified = current;
+                last_activity = Instant::now();
+                match fs::read_to_string(&path) {
+                    Ok(content) => {
+                        saved = true;
+                        send(Event::Changed(target, content));
+                    }
+                    Err(e) => send(Event::Failed(target, e.to_string())),
+                }
+            }
+
+            let done = if detached {
+                last_activity.elapsed() > DETACHED_TIMEOUT
+            } else {
+                exited
+            };
+            if stopping || done {
+                break;
+            }
+        }
+
+        drop(path);
+        send(Event::Closed(target));
+    });
+    Ok(session)
+}
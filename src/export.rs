@@ -0,0 +1,370 @@
+//! Saving responses and whole sessions to files.
+
+use eframe::egui;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use crate::gia::GiaResult;
+use crate::markdown;
+use crate::template;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Markdown,
+    Text,
+    Html,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Markdown, Format::Text, Format::Html, Format::Json];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Markdown => "Markdown",
+            Format::Text => "Plain text",
+            Format::Html => "HTML",
+            Format::Json => "JSON",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Text => "txt",
+            Format::Html => "html",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// Where exports go and how the files are named.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    pub directory: String,
+    /// File name without extension; see `file_name` for the placeholders.
+    pub file_name: String,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        let directory = dirs::document_dir()
+            .or_else(dirs::home_dir)
+            .map(|dir| dir.join("giagui").display().to_string())
+            .unwrap_or_default();
+        Self {
+            directory,
+            file_name: "{date}_{time}_{model}_{title}".to_string(),
+        }
+    }
+}
+
+/// Expands `{date}`, `{time}` (both UTC), `{model}`, `{task}`, `{role}` and
+/// `{title}` (the start of the prompt) for `result`, keeping only characters safe
+/// in file names, and appends `extension`.
+pub fn file_name(template: &str, result: &GiaResult, extension: &str) -> String {
+    let secs = if result.started_at > 0 {
+        result.started_at
+    } else {
+        now()
+    };
+    let time = format!(
+        "{:02}-{:02}-{:02}",
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    );
+    let title: String = result
+        .request
+        .prompt
+        .split_whitespace()
+        .take(6)
+        .collect::<Vec<_>>()
+        .join("-");
+
+    let name = template
+        .replace("{date}", &template::format_date(secs))
+        .replace("{time}", &time)
+        .replace("{model}", &result.request.model)
+        .replace("{task}", &result.request.task)
+        .replace("{role}", &result.request.role)
+        .replace("{title}", &title);
+    let mut name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name = name.trim_matches(['_', '-', ' ', '.']).to_string();
+    if name.is_empty() {
+        name = "response".to_string();
+    }
+    format!("{}.{}", name, extension.trim_start_matches('.'))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn metadata_line(result: &GiaResult) -> String {
+    let mut parts = vec![result.request.model.clone()];
+    if !result.request.task.is_empty() {
+        parts.push(format!("task {}", result.request.task));
+    }
+    if !result.request.role.is_empty() {
+        parts.push(format!("role {}", result.request.role));
+    }
+    if result.started_at > 0 {
+        parts.push(template::format_date(result.started_at));
+    }
+    parts.push(format!("{:.1} s", result.duration_ms as f64 / 1000.0));
+    parts.join(" · ")
+}
+
+fn to_markdown(results: &[GiaResult]) -> String {
+    results
+        .iter()
+        .map(|result| {
+            format!(
+                "## Prompt\n\n{}\n\n_{}_\n\n## Response\n\n{}\n",
+                result.request.prompt.trim(),
+                metadata_line(result),
+                result.response_text().trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n---\n\n")
+}
+
+fn to_text(results: &[GiaResult]) -> String {
+    results
+        .iter()
+        .map(|result| {
+            format!(
+                "Prompt:\n{}\n\n[{}]\n\nResponse:\n{}\n",
+                result.request.prompt.trim(),
+                metadata_line(result),
+                result.response_text().trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n========================================\n\n")
+}
+
+/// Renders Markdown to HTML with syntax highlighted code blocks.
+fn markdown_to_html(md: &str, syntaxes: &SyntaxSet, themes: &ThemeSet) -> String {
+    let theme = &themes.themes["InspiredGitHub"];
+    let mut code: Option<(String, String)> = None;
+    let mut events = Vec::new();
+
+    for event in Parser::new_ext(md, markdown::parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, body)) = &mut code {
+                    body.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let Some((language, body)) = code.take() else {
+                    continue;
+                };
+                let syntax = syntaxes
+                    .find_syntax_by_token(&language)
+                    .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+                let html = highlighted_html_for_string(&body, syntaxes, syntax, theme)
+                    .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", escape_html(&body)));
+                events.push(Event::Html(CowStr::from(html)));
+            }
+            // Raw HTML from the model is shown as text so `<script>` stays inert
+            Event::Html(html) | Event::InlineHtml(html) => events.push(Event::Text(html)),
+            event => events.push(event),
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn to_html(results: &[GiaResult]) -> String {
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let themes = ThemeSet::load_defaults();
+
+    let sections: Vec<String> = results
+        .iter()
+        .map(|result| {
+            format!(
+                "<section>\n<h2>Prompt</h2>\n<pre class=\"prompt\">{}</pre>\n\
+                 <p class=\"meta\">{}</p>\n<h2>Response</h2>\n{}</section>\n",
+                escape_html(result.request.prompt.trim()),
+                escape_html(&metadata_line(result)),
+                markdown_to_html(&result.response_text(), &syntaxes, &themes)
+            )
+        })
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>GIA GUI export</title>\n\
+         <style>\n\
+         body {{ font-family: sans-serif; max-width: 60em; margin: 2em auto; line-height: 1.5; }}\n\
+         pre {{ padding: 0.8em; overflow-x: auto; border-radius: 4px; }}\n\
+         pre.prompt {{ background: #f4f4f4; white-space: pre-wrap; }}\n\
+         .meta {{ color: #777; font-style: italic; }}\n\
+         section + section {{ border-top: 1px solid #ddd; margin-top: 2em; }}\n\
+         </style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        sections.join("")
+    )
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    exported_at: u64,
+    responses: &'a [GiaResult],
+}
+
+pub fn render(results: &[GiaResult], format: Format) -> Result<String, String> {
+    Ok(match format {
+        Format::Markdown => to_markdown(results),
+        Format::Text => to_text(results),
+        Format::Html => to_html(results),
+        Format::Json => serde_json::to_string_pretty(&JsonExport {
+            exported_at: now(),
+            responses: results,
+        })
+        .map_err(|e| format!("Failed to serialize export: {}", e))?,
+    })
+}
+
+/// Writes `content` to `directory/file_name`, adding `-2`, `-3`, ... instead of
+/// overwriting an existing file. Returns the path written.
+pub fn write(directory: &Path, file_name: &str, content: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+
+    let mut path = directory.join(file_name);
+    let (stem, extension) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+    let mut counter = 2;
+    while path.exists() {
+        path = directory.join(format!("{}-{}.{}", stem, counter, extension));
+        counter += 1;
+    }
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub enum DialogAction {
+    Export,
+    SaveDefaults,
+}
+
+/// Window choosing what to export, in which format and where.
+#[derive(Default)]
+pub struct Dialog {
+    pub open: bool,
+    /// Export every turn of the session instead of the shown response.
+    pub session: bool,
+    pub format: Format,
+    pub settings: ExportSettings,
+}
+
+impl Dialog {
+    /// `sample` is used to preview the file name.
+    pub fn show(&mut self, ctx: &egui::Context, sample: &GiaResult) -> Option<DialogAction> {
+        if !self.open {
+            return None;
+        }
+
+        let mut open = self.open;
+        let mut action = None;
+        egui::Window::new("Export")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("export_options")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Export");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.session, false, "Current response");
+                            ui.radio_value(&mut self.session, true, "Whole session");
+                        });
+                        ui.end_row();
+
+                        ui.label("Format");
+                        egui::ComboBox::from_id_salt("export_format")
+                            .selected_text(self.format.label())
+                            .show_ui(ui, |ui| {
+                                for format in Format::ALL {
+                                    ui.selectable_value(&mut self.format, format, format.label());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Directory");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.settings.directory)
+                                .desired_width(320.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("File name");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.settings.file_name)
+                                .desired_width(320.0),
+                        )
+                        .on_hover_text("{date} {time} (UTC) {model} {task} {role} {title}");
+                        ui.end_row();
+                    });
+                ui.weak(file_name(
+                    &self.settings.file_name,
+                    sample,
+                    self.format.extension(),
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        action = Some(DialogAction::Export);
+                    }
+                    if ui.button("Save as default").clicked() {
+                        action = Some(DialogAction::SaveDefaults);
+                    }
+                });
+            });
+
+        self.open = open && !matches!(action, Some(DialogAction::Export));
+        action
+    }
+}
@@ -0,0 +1,164 @@
+//! Fuzzy matching and a keyboard-driven picker popup.
+
+use eframe::egui;
+
+/// Scores `candidate` against `query` as a case-insensitive subsequence match.
+/// Returns `None` if not all query characters appear in order. Higher is better.
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        if query_char.is_whitespace() {
+            continue;
+        }
+        let index = (position..candidate.len())
+            .find(|&i| candidate[i].to_lowercase().eq(std::iter::once(query_char)))?;
+
+        score += 10;
+        let at_boundary = index == 0
+            || matches!(candidate[index - 1], '/' | '-' | '_' | ' ' | '.')
+            || (candidate[index].is_uppercase() && candidate[index - 1].is_lowercase());
+        if at_boundary {
+            score += 8;
+        }
+        match previous_match {
+            Some(previous) if previous + 1 == index => score += 6,
+            Some(previous) => score -= (index - previous - 1).min(5) as i32,
+            None => score -= index.min(10) as i32,
+        }
+
+        previous_match = Some(index);
+        position = index + 1;
+    }
+
+    // Prefer shorter candidates among otherwise equal matches
+    Some(score - (candidate.len() as i32 / 8))
+}
+
+pub struct PickerItem {
+    pub label: String,
+    /// Secondary text shown to the right, e.g. a shortcut or description.
+    pub detail: String,
+}
+
+/// Popup with a search field and a filtered list; arrows move, Enter picks,
+/// Escape closes.
+#[derive(Default)]
+pub struct Picker {
+    open: bool,
+    query: String,
+    highlighted: usize,
+}
+
+impl Picker {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.query.clear();
+        self.highlighted = 0;
+    }
+
+    /// Shows the picker and returns the index into `items` of the chosen entry.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        title: &str,
+        items: &[PickerItem],
+    ) -> Option<usize> {
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(usize, i32)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let score = score(&self.query, &item.label)
+                    .or_else(|| score(&self.query, &item.detail).map(|score| score - 20))?;
+                Some((index, score))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        self.highlighted = self.highlighted.min(matches.len().saturating_sub(1));
+
+        // Consume navigation keys before the text field sees them
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if up {
+            self.highlighted = self.highlighted.saturating_sub(1);
+        }
+        if down && self.highlighted + 1 < matches.len() {
+            self.highlighted += 1;
+        }
+        let mut chosen = if enter {
+            matches.get(self.highlighted).map(|(index, _)| *index)
+        } else {
+            None
+        };
+
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(title);
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type to filter")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.highlighted = 0;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        if matches.is_empty() {
+                            ui.weak("No matches");
+                        }
+                        for (position, (index, _)) in matches.iter().enumerate() {
+                            let item = &items[*index];
+                            let highlighted = position == self.highlighted;
+                            let response = ui
+                                .horizontal(|ui| {
+                                    let label = ui.selectable_label(highlighted, &item.label);
+                                    if !item.detail.is_empty() {
+                                        ui.weak(&item.detail);
+                                    }
+                                    label
+                                })
+                                .inner;
+                            if highlighted && (up || down) {
+                                response.scroll_to_me(None);
+                            }
+                            if response.clicked() {
+                                chosen = Some(*index);
+                            }
+                        }
+                    });
+            });
+
+        self.open = !escape && chosen.is_none();
+        chosen
+    }
+}
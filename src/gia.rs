@@ -0,0 +1,166 @@
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Everything needed to run gia once, captured when the user sends.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GiaRequest {
+    /// The gia executable.
+    pub program: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    pub prompt: String,
+    pub model: String,
+    pub task: String,
+    pub role: String,
+    /// Tasks and roles rendered by giagui (filled-in placeholders, several
+    /// selections or stripped front matter). Not stored anywhere: each run
+    /// writes them to temp files that are deleted once gia exits.
+    #[serde(skip)]
+    pub generated: Vec<Generated>,
+}
+
+/// A task or role file made for a request. `args[arg]` is replaced by the
+/// path of the temp file holding `content`.
+#[derive(Clone, Debug)]
+pub struct Generated {
+    pub arg: usize,
+    pub content: String,
+}
+
+/// Writes `generated` to a private temp file and returns it together with the
+/// name to pass to gia: the absolute path without `.md`, since gia looks names
+/// up as `<name>.md` in its own folders.
+fn write_generated(generated: &Generated) -> Result<(tempfile::TempPath, String), String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("giagui-")
+        .suffix(".md")
+        .tempfile()
+        .map_err(|e| format!("Failed to create a temp file: {}", e))?;
+    file.write_all(generated.content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", file.path().display(), e))?;
+    let path = file.into_temp_path();
+    let name = path.with_extension("").display().to_string();
+    Ok((path, name))
+}
+
+/// Outcome of a single gia invocation.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GiaResult {
+    pub request: GiaRequest,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    /// Set when gia could not be started at all.
+    pub error: Option<String>,
+    /// Seconds since the Unix epoch when the run started.
+    pub started_at: u64,
+    pub duration_ms: u64,
+}
+
+impl GiaResult {
+    /// Text shown in the response box.
+    pub fn response_text(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("Error executing gia: {}", error);
+        }
+
+        let mut response = self.stdout.clone();
+        if !self.stderr.is_empty() {
+            response.push_str("\n\nErrors:\n");
+            response.push_str(&self.stderr);
+        }
+        response
+    }
+}
+
+pub fn run(request: GiaRequest) -> GiaResult {
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let start = Instant::now();
+    let mut result = GiaResult {
+        started_at,
+        ..Default::default()
+    };
+
+    // Deleted when dropped, after gia exited
+    let mut temp_files = Vec::new();
+    let mut args = request.args.clone();
+    for generated in &request.generated {
+        match write_generated(generated) {
+            Ok((path, name)) => {
+                args[generated.arg] = name;
+                temp_files.push(path);
+            }
+            Err(e) => {
+                result.error = Some(e);
+                result.request = request;
+                return result;
+            }
+        }
+    }
+
+    let mut command = Command::new(&request.program);
+    if let Some(dir) = &request.working_dir {
+        command.current_dir(dir);
+    }
+    let output = command.args(&args).output();
+    drop(temp_files);
+
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    match output {
+        Ok(output) => {
+            result.stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            result.stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            result.exit_code = output.status.code();
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result.request = request;
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn generated_tasks_live_in_a_temp_file_while_gia_runs() {
+        // Prints the task file gia would read, then its name
+        let request = GiaRequest {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"cat "$1.md" && echo && echo "$1""#.to_string(),
+                "gia".to_string(),
+                "group/review + other/style".to_string(),
+            ],
+            generated: vec![Generated {
+                arg: 3,
+                content: "combined task".to_string(),
+            }],
+            ..Default::default()
+        };
+        let result = run(request);
+        assert_eq!(result.error, None);
+
+        let mut lines = result.stdout.lines();
+        assert_eq!(lines.next(), Some("combined task"));
+        let name = lines.next().unwrap();
+        let path = format!("{}.md", name);
+        assert!(Path::new(name).is_absolute(), "{}", name);
+        assert!(
+            Path::new(&path).starts_with(std::env::temp_dir()),
+            "{}",
+            path
+        );
+        assert!(!Path::new(&path).exists(), "{} was not deleted", path);
+        // The request keeps the names, e.g. for regenerating
+        assert_eq!(result.request.args[3], "group/review + other/style");
+    }
+}
@@ -0,0 +1,112 @@
+//! Responses received in this session.
+
+use crate::gia::GiaResult;
+
+/// All responses to one prompt: the original and its regenerated variants.
+pub struct Turn {
+    /// Stays the same while older turns are dropped.
+    pub id: u64,
+    pub variants: Vec<GiaResult>,
+    /// Index of the variant shown in the response box.
+    pub shown: usize,
+    pub preferred: Option<usize>,
+}
+
+impl Turn {
+    pub fn new(id: u64, result: GiaResult) -> Self {
+        Self {
+            id,
+            variants: vec![result],
+            shown: 0,
+            preferred: None,
+        }
+    }
+
+    /// Adds a regenerated variant and shows it.
+    pub fn push(&mut self, result: GiaResult) {
+        self.variants.push(result);
+        self.shown = self.variants.len() - 1;
+    }
+
+    pub fn current(&self) -> &GiaResult {
+        &self.variants[self.shown]
+    }
+
+    /// Moves `offset` variants forward or back; returns whether it moved.
+    pub fn step(&mut self, offset: isize) -> bool {
+        match self.shown.checked_add_signed(offset) {
+            Some(shown) if shown < self.variants.len() => {
+                self.shown = shown;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Marks the shown variant as preferred, or clears the mark if it already is.
+    pub fn toggle_preferred(&mut self) {
+        self.preferred = if self.preferred == Some(self.shown) {
+            None
+        } else {
+            Some(self.shown)
+        };
+    }
+}
+
+/// Every turn of this session, oldest first.
+#[derive(Default)]
+pub struct History {
+    pub turns: Vec<Turn>,
+    next_id: u64,
+}
+
+impl History {
+    /// Starts a new turn with `result` and returns its id.
+    pub fn push(&mut self, result: GiaResult) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.turns.push(Turn::new(id, result));
+        id
+    }
+
+    pub fn turn_mut(&mut self, id: u64) -> Option<&mut Turn> {
+        self.turns.iter_mut().find(|turn| turn.id == id)
+    }
+
+    /// Drops the oldest turns beyond `max_turns`; 0 keeps all.
+    pub fn truncate(&mut self, max_turns: usize) {
+        if max_turns > 0 && self.turns.len() > max_turns {
+            self.turns.drain(..self.turns.len() - max_turns);
+        }
+    }
+
+    pub fn current(&self) -> Option<&Turn> {
+        self.turns.last()
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut Turn> {
+        self.turns.last_mut()
+    }
+
+    /// All stored responses with a label, oldest first.
+    pub fn entries(&self) -> Vec<(String, &GiaResult)> {
+        self.turns
+            .iter()
+            .enumerate()
+            .flat_map(|(turn_index, turn)| {
+                turn.variants
+                    .iter()
+                    .enumerate()
+                    .map(move |(variant_index, result)| {
+                        let mut label = format!("#{}", turn_index + 1);
+                        if turn.variants.len() > 1 {
+                            label.push_str(&format!(".{}", variant_index + 1));
+                        }
+                        let prompt: String = result.request.prompt.chars().take(40).collect();
+                        label.push_str(&format!(" {} · {}", result.request.model, prompt));
+                        (label, result)
+                    })
+            })
+            .collect()
+    }
+}
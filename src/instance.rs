@@ -0,0 +1,301 @@
+//! Single-instance support: a second launch forwards its arguments to the
+//! running window over a local socket and exits.
+//!
+//! The first instance holds an OS lock on a lock file for as long as it runs,
+//! so two simultaneous launches cannot both become the primary window.
+
+use eframe::egui;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli::CliArgs;
+
+pub type RequestQueue = Arc<Mutex<VecDeque<CliArgs>>>;
+
+const ACK: &[u8] = b"ok";
+
+/// How long a second launch waits for a starting primary to accept requests.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long the primary waits for a forwarded request, so a client that never
+/// finishes sending cannot hold up the next one.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn encode(secret: &str, args: &CliArgs) -> Result<String, String> {
+    let args = toml::to_string(args).map_err(|e| format!("Failed to encode request: {}", e))?;
+    Ok(format!("{}\n{}", secret, args))
+}
+
+/// The forwarded arguments, if the request carries the expected secret.
+fn decode(secret: &str, content: &str) -> Option<CliArgs> {
+    let (sent, args) = content.split_once('\n')?;
+    if !same_secret(sent, secret) {
+        return None;
+    }
+    toml::from_str(args).ok()
+}
+
+/// Compares in constant time so the secret cannot be guessed byte by byte.
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn send_over(mut stream: Stream, secret: &str, args: &CliArgs) -> Result<(), String> {
+    stream
+        .write_all(encode(secret, args)?.as_bytes())
+        .map_err(|e| format!("Failed to forward request: {}", e))?;
+    finish_write(&stream);
+
+    let mut reply = Vec::new();
+    let _ = stream.read_to_end(&mut reply);
+    if reply == ACK {
+        Ok(())
+    } else {
+        Err("Running instance did not acknowledge the request".to_string())
+    }
+}
+
+fn handle_connection(mut stream: Stream, secret: &str, queue: &RequestQueue, ctx: &egui::Context) {
+    set_read_timeout(&stream, READ_TIMEOUT);
+    let mut content = String::new();
+    if stream.read_to_string(&mut content).is_err() {
+        return;
+    }
+    if let Some(args) = decode(secret, &content) {
+        queue.lock().unwrap().push_back(args);
+        let _ = stream.write_all(ACK);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        ctx.request_repaint();
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs::{self, DirBuilder};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+
+    pub type Listener = UnixListener;
+    pub type Stream = UnixStream;
+
+    pub fn user() -> String {
+        std::env::var("USER").unwrap_or_default()
+    }
+
+    fn uid() -> u32 {
+        // SAFETY: getuid cannot fail and has no side effects
+        unsafe { libc::getuid() }
+    }
+
+    fn owned_by_user(path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|metadata| metadata.uid() == uid())
+    }
+
+    /// A directory only this user can enter. Without `XDG_RUNTIME_DIR` it lives
+    /// in the shared temp directory, so one created by someone else is refused
+    /// rather than used.
+    pub fn runtime_dir() -> Result<PathBuf, String> {
+        let base = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+        let dir = base.join(format!("giagui-{}", uid()));
+        match DirBuilder::new().mode(0o700).create(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(format!("Failed to create {}: {}", dir.display(), e)),
+        }
+        let metadata = fs::symlink_metadata(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        if !metadata.is_dir() || metadata.uid() != uid() || metadata.mode() & 0o077 != 0 {
+            return Err(format!(
+                "{} is not a private directory of the current user",
+                dir.display()
+            ));
+        }
+        Ok(dir)
+    }
+
+    fn socket_path() -> Result<PathBuf, String> {
+        Ok(runtime_dir()?.join(format!("giagui-{}.sock", user())))
+    }
+
+    /// Connects to the running instance and returns the secret it expects.
+    /// Access is limited by the private directory and the socket's owner, so
+    /// none is needed.
+    pub fn connect() -> Option<(Stream, String)> {
+        let path = socket_path().ok()?;
+        if !owned_by_user(&path) {
+            return None;
+        }
+        let stream = UnixStream::connect(path).ok()?;
+        Some((stream, String::new()))
+    }
+
+    /// Binds the socket. Only called while holding the instance lock, so an
+    /// existing socket file is left over from a crashed instance - unless
+    /// something still answers on it.
+    pub fn listen() -> Result<(Listener, String), String> {
+        let path = socket_path()?;
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!(
+                "Another giagui instance is listening on {}",
+                path.display()
+            ));
+        }
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| format!("Failed to listen on {}: {}", path.display(), e))?;
+        Ok((listener, String::new()))
+    }
+
+    pub fn finish_write(stream: &Stream) {
+        let _ = stream.shutdown(std::net::Shutdown::Write);
+    }
+
+    pub fn set_read_timeout(stream: &Stream, timeout: std::time::Duration) {
+        let _ = stream.set_read_timeout(Some(timeout));
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::fs;
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::path::PathBuf;
+
+    pub type Listener = TcpListener;
+    pub type Stream = TcpStream;
+
+    pub fn user() -> String {
+        std::env::var("USERNAME").unwrap_or_default()
+    }
+
+    /// The per-user local data directory, which other users cannot read.
+    pub fn runtime_dir() -> Result<PathBuf, String> {
+        let dir = dirs::data_local_dir()
+            .map(|dir| dir.join("giagui"))
+            .unwrap_or_else(std::env::temp_dir);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(dir)
+    }
+
+    /// Holds the port and the secret any local process must send along, since
+    /// a localhost port is open to every user on the machine.
+    fn address_path() -> Result<PathBuf, String> {
+        Ok(runtime_dir()?.join(format!("giagui-{}.address", user())))
+    }
+
+    pub fn connect() -> Option<(Stream, String)> {
+        let content = fs::read_to_string(address_path().ok()?).ok()?;
+        let (port, secret) = content.trim().split_once('\n')?;
+        let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port.trim().parse().ok()?)).ok()?;
+        Some((stream, secret.trim().to_string()))
+    }
+
+    pub fn listen() -> Result<(Listener, String), String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .map_err(|e| format!("Failed to listen on localhost: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to listen on localhost: {}", e))?
+            .port();
+
+        let mut bytes = [0u8; 32];
+        getrandom::fill(&mut bytes)
+            .map_err(|e| format!("Failed to generate instance secret: {}", e))?;
+        let secret: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        let path = address_path()?;
+        fs::write(&path, format!("{}\n{}", port, secret))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok((listener, secret))
+    }
+
+    pub fn finish_write(stream: &Stream) {
+        let _ = stream.shutdown(std::net::Shutdown::Write);
+    }
+
+    pub fn set_read_timeout(stream: &Stream, timeout: std::time::Duration) {
+        let _ = stream.set_read_timeout(Some(timeout));
+    }
+}
+
+use platform::{Stream, finish_write, set_read_timeout};
+
+/// The primary instance's end of the socket, together with the instance lock
+/// which is released when the process exits.
+pub struct Listener {
+    listener: platform::Listener,
+    secret: String,
+    _lock: File,
+}
+
+fn lock_path() -> Result<PathBuf, String> {
+    Ok(platform::runtime_dir()?.join(format!("giagui-{}.lock", platform::user())))
+}
+
+/// Tries to become the primary instance. Returns `None` when another instance
+/// holds the lock, in which case requests should be forwarded to it.
+pub fn claim() -> Result<Option<Listener>, String> {
+    let path = lock_path()?;
+    let lock = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => return Ok(None),
+        Err(fs::TryLockError::Error(e)) => {
+            return Err(format!("Failed to lock {}: {}", path.display(), e));
+        }
+    }
+
+    let (listener, secret) = platform::listen()?;
+    Ok(Some(Listener {
+        listener,
+        secret,
+        _lock: lock,
+    }))
+}
+
+/// Forwards `args` to the running instance, waiting briefly in case it is
+/// still starting up.
+pub fn forward(args: &CliArgs) -> Result<(), String> {
+    let started = Instant::now();
+    loop {
+        if let Some((stream, secret)) = platform::connect() {
+            return send_over(stream, &secret, args);
+        }
+        if started.elapsed() > CONNECT_TIMEOUT {
+            return Err(
+                "Another giagui instance is running but does not accept requests".to_string(),
+            );
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Accepts forwarded requests in the background and queues them for the UI.
+/// Each connection is read on its own thread, so a slow client does not delay
+/// the others.
+pub fn serve(listener: Listener, queue: RequestQueue, ctx: egui::Context) {
+    thread::spawn(move || {
+        let secret: Arc<str> = listener.secret.as_str().into();
+        for stream in listener.listener.incoming().flatten() {
+            let (secret, queue, ctx) = (Arc::clone(&secret), Arc::clone(&queue), ctx.clone());
+            thread::spawn(move || handle_connection(stream, &secret, &queue, &ctx));
+        }
+    });
+}
@@ -0,0 +1,528 @@
+//! User-configurable key bindings for the commands.
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::commands::{self, Command, Scope};
+
+/// Bindings that differ from the defaults, by command id. An empty string
+/// unbinds the command; a `global:`, `prompt:` or `response:` prefix sets where
+/// the binding applies, otherwise the command's default scope is kept.
+pub type Overrides = BTreeMap<String, String>;
+
+/// Parses `Ctrl+Shift+P`-style text; an empty string or `none` means unbound.
+pub fn parse(text: &str) -> Result<Option<KeyboardShortcut>, String> {
+    let text = text.trim();
+    if text.is_empty() || text.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+
+    // `Ctrl++` binds the plus key
+    let (modifier_names, key_name) = if let Some(prefix) = text.strip_suffix("++") {
+        (prefix, "+")
+    } else {
+        text.rsplit_once('+').unwrap_or(("", text))
+    };
+
+    let mut modifiers = Modifiers::NONE;
+    for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+        modifiers |= match name.trim().to_lowercase().as_str() {
+            "ctrl" | "control" => Modifiers::CTRL,
+            "shift" => Modifiers::SHIFT,
+            "alt" | "option" => Modifiers::ALT,
+            "cmd" | "command" | "super" => Modifiers::MAC_CMD,
+            _ => return Err(format!("Unknown modifier '{}' in '{}'", name, text)),
+        };
+    }
+    let key = Key::from_name(key_name.trim())
+        .ok_or_else(|| format!("Unknown key '{}' in '{}'", key_name, text))?;
+    Ok(Some(KeyboardShortcut::new(modifiers, key)))
+}
+
+/// Parses a binding with an optional scope prefix, e.g. `prompt: Ctrl+Enter`.
+pub fn parse_binding(text: &str) -> Result<(Option<KeyboardShortcut>, Option<Scope>), String> {
+    match text.split_once(':') {
+        Some((scope, shortcut)) => {
+            let scope = Scope::from_id(&scope.trim().to_lowercase())
+                .ok_or_else(|| format!("Unknown scope '{}' in '{}'", scope.trim(), text))?;
+            Ok((parse(shortcut)?, Some(scope)))
+        }
+        None => Ok((parse(text)?, None)),
+    }
+}
+
+/// Whether `shortcut` is also a common text editing key, so binding it takes
+/// that key away from the text fields.
+pub fn clashes_with_text_editing(shortcut: &KeyboardShortcut) -> bool {
+    let modifiers = shortcut.modifiers;
+    let ctrl = modifiers.ctrl || modifiers.mac_cmd || modifiers.command;
+    // Home/End, also with Ctrl to jump to either end of the text
+    if matches!(shortcut.logical_key, Key::Home | Key::End) {
+        return true;
+    }
+    if !ctrl && !modifiers.alt {
+        // Plain and Shift+ keys are typing, except the function keys
+        return !matches!(
+            shortcut.logical_key,
+            Key::F1
+                | Key::F2
+                | Key::F3
+                | Key::F4
+                | Key::F5
+                | Key::F6
+                | Key::F7
+                | Key::F8
+                | Key::F9
+                | Key::F10
+                | Key::F11
+                | Key::F12
+        );
+    }
+    match shortcut.logical_key {
+        // Select all, copy, paste, cut, undo, redo (Ctrl+Y or Ctrl+Shift+Z)
+        Key::A | Key::C | Key::V | Key::X | Key::Y => ctrl && !modifiers.alt && !modifiers.shift,
+        Key::Z => ctrl && !modifiers.alt,
+        // Emacs-style deletion: character, to line end, to line start, word
+        Key::H | Key::K | Key::U | Key::W => ctrl && !modifiers.alt && !modifiers.shift,
+        // Moving and selecting by word or line (Alt on macOS), and deleting
+        // by word
+        Key::ArrowLeft
+        | Key::ArrowRight
+        | Key::ArrowUp
+        | Key::ArrowDown
+        | Key::Backspace
+        | Key::Delete => true,
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<Command, Option<KeyboardShortcut>>,
+    scopes: HashMap<Command, Scope>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Command::ALL
+                .iter()
+                .map(|&command| (command, command.default_shortcut()))
+                .collect(),
+            scopes: Command::ALL
+                .iter()
+                .map(|&command| (command, command.default_scope()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// The default bindings with `overrides` applied. Invalid entries are
+    /// skipped and reported.
+    pub fn new(overrides: &Overrides) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut errors = Vec::new();
+
+        for (id, text) in overrides {
+            let Some(command) = Command::from_id(id) else {
+                errors.push(format!("Unknown command '{}' in [shortcuts]", id));
+                continue;
+            };
+            match parse_binding(text) {
+                Ok((shortcut, scope)) => {
+                    keymap.set(command, shortcut);
+                    if let Some(scope) = scope {
+                        keymap.set_scope(command, scope);
+                    }
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        for commands in keymap.conflicts() {
+            let labels: Vec<String> = commands.iter().map(|c| c.label()).collect();
+            errors.push(format!("Shortcut conflict: {}", labels.join(", ")));
+        }
+        (keymap, errors)
+    }
+
+    pub fn get(&self, command: Command) -> Option<KeyboardShortcut> {
+        self.bindings.get(&command).copied().flatten()
+    }
+
+    pub fn set(&mut self, command: Command, shortcut: Option<KeyboardShortcut>) {
+        self.bindings.insert(command, shortcut);
+    }
+
+    pub fn scope(&self, command: Command) -> Scope {
+        self.scopes.get(&command).copied().unwrap_or_default()
+    }
+
+    pub fn set_scope(&mut self, command: Command, scope: Scope) {
+        self.scopes.insert(command, scope);
+    }
+
+    /// The bindings that differ from the defaults, for the config file.
+    pub fn overrides(&self) -> Overrides {
+        Command::ALL
+            .iter()
+            .filter(|command| {
+                self.get(**command) != command.default_shortcut()
+                    || self.scope(**command) != command.default_scope()
+            })
+            .map(|&command| {
+                let shortcut = self
+                    .get(command)
+                    .map(|shortcut| commands::format_shortcut(&shortcut))
+                    .unwrap_or_default();
+                let scope = self.scope(command);
+                let text = if scope == command.default_scope() {
+                    shortcut
+                } else {
+                    format!("{}: {}", scope.id(), shortcut)
+                };
+                (command.id(), text)
+            })
+            .collect()
+    }
+
+    /// The commands pressed this frame, given which field has focus. Their key
+    /// events are consumed.
+    ///
+    /// Bindings for the focused field come first so they shadow global ones.
+    /// While a text field has focus, global bindings that are also text
+    /// editing keys are left to the field.
+    pub fn take_pressed(
+        &self,
+        input: &mut egui::InputState,
+        focus: Scope,
+        typing: bool,
+    ) -> Vec<Command> {
+        let scoped = Command::ALL
+            .iter()
+            .filter(|&&command| focus != Scope::Global && self.scope(command) == focus);
+        let global = Command::ALL
+            .iter()
+            .filter(|&&command| self.scope(command) == Scope::Global);
+
+        let mut pressed = Vec::new();
+        for &command in scoped.chain(global) {
+            let Some(shortcut) = self.get(command) else {
+                continue;
+            };
+            if typing
+                && self.scope(command) == Scope::Global
+                && clashes_with_text_editing(&shortcut)
+            {
+                continue;
+            }
+            if commands::consume(input, &shortcut) {
+                pressed.push(command);
+            }
+        }
+        pressed
+    }
+
+    /// Commands bound to the same shortcut in the same scope as `command`,
+    /// besides itself.
+    pub fn conflicting(&self, command: Command) -> Vec<Command> {
+        let Some(shortcut) = self.get(command) else {
+            return Vec::new();
+        };
+        let scope = self.scope(command);
+        Command::ALL
+            .iter()
+            .copied()
+            .filter(|&other| {
+                other != command && self.get(other) == Some(shortcut) && self.scope(other) == scope
+            })
+            .collect()
+    }
+
+    /// Groups of commands sharing a shortcut in the same scope.
+    pub fn conflicts(&self) -> Vec<Vec<Command>> {
+        let mut by_shortcut: BTreeMap<(&str, String), Vec<Command>> = BTreeMap::new();
+        for &command in Command::ALL {
+            if let Some(shortcut) = self.get(command) {
+                by_shortcut
+                    .entry((
+                        self.scope(command).id(),
+                        commands::format_shortcut(&shortcut),
+                    ))
+                    .or_default()
+                    .push(command);
+            }
+        }
+        by_shortcut
+            .into_values()
+            .filter(|commands| commands.len() > 1)
+            .collect()
+    }
+}
+
+/// Editing a copy of the keymap; shortcuts are recorded by pressing them.
+#[derive(Default)]
+pub struct Editor {
+    draft: Keymap,
+    /// Command waiting for its new shortcut.
+    capturing: Option<Command>,
+}
+
+impl Editor {
+    pub fn load(&mut self, keymap: &Keymap) {
+        self.draft = keymap.clone();
+        self.capturing = None;
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.draft
+    }
+
+    /// While recording, key presses must not trigger commands.
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.is_some()
+    }
+
+    pub fn stop_capturing(&mut self) {
+        self.capturing = None;
+    }
+
+    /// The binding table, followed by the conflicts that block saving.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        self.capture(ui.ctx());
+
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 40.0)
+            .show(ui, |ui| {
+                egui::Grid::new("keymap")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for &command in Command::ALL {
+                            self.row(ui, command);
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        for commands in self.draft.conflicts() {
+            let labels: Vec<String> = commands.iter().map(|c| c.label()).collect();
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("Conflict: {}", labels.join(", ")),
+            );
+        }
+    }
+
+    fn row(&mut self, ui: &mut egui::Ui, command: Command) {
+        ui.label(command.label());
+
+        let text = if self.capturing == Some(command) {
+            "Press keys... (Esc cancels)".to_string()
+        } else {
+            self.draft
+                .get(command)
+                .map(|shortcut| commands::format_shortcut(&shortcut))
+                .unwrap_or_else(|| "—".to_string())
+        };
+        let conflicting = self.draft.conflicting(command);
+        let mut button = egui::Button::new(text).min_size([180.0, 0.0].into());
+        if !conflicting.is_empty() {
+            button = button.fill(ui.visuals().error_fg_color.gamma_multiply(0.3));
+        }
+        let response = ui.add(button);
+        if response.clicked() {
+            self.capturing = Some(command);
+        }
+        if !conflicting.is_empty() {
+            let labels: Vec<String> = conflicting.iter().map(|c| c.label()).collect();
+            response.on_hover_text(format!("Also bound to: {}", labels.join(", ")));
+        }
+
+        let mut scope = self.draft.scope(command);
+        egui::ComboBox::from_id_salt(("keymap_scope", command.id()))
+            .selected_text(scope.label())
+            .show_ui(ui, |ui| {
+                for option in Scope::ALL {
+                    ui.selectable_value(&mut scope, option, option.label());
+                }
+            });
+        self.draft.set_scope(command, scope);
+
+        ui.horizontal(|ui| {
+            if ui.small_button("Clear").clicked() {
+                self.draft.set(command, None);
+            }
+            if ui.small_button("Default").clicked() {
+                self.draft.set(command, command.default_shortcut());
+                self.draft.set_scope(command, command.default_scope());
+            }
+            if self
+                .draft
+                .get(command)
+                .is_some_and(|shortcut| clashes_with_text_editing(&shortcut))
+            {
+                ui.weak("⚠").on_hover_text(if scope == Scope::Global {
+                    "Also a text editing key: ignored while typing in a text field"
+                } else {
+                    "Also a text editing key: replaces it in this field"
+                });
+            }
+        });
+    }
+
+    /// Records the next key press as the shortcut of the command being edited.
+    fn capture(&mut self, ctx: &egui::Context) {
+        let Some(command) = self.capturing else {
+            return;
+        };
+        let pressed = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => Some((*key, *modifiers)),
+                _ => None,
+            })
+        });
+        let Some((key, modifiers)) = pressed else {
+            return;
+        };
+        ctx.input_mut(|i| i.consume_key(modifiers, key));
+
+        if key == Key::Escape && modifiers.is_none() {
+            self.capturing = None;
+            return;
+        }
+        let modifiers = Modifiers {
+            alt: modifiers.alt,
+            ctrl: modifiers.ctrl,
+            shift: modifiers.shift,
+            mac_cmd: modifiers.mac_cmd,
+            command: false,
+        };
+        self.draft
+            .set(command, Some(KeyboardShortcut::new(modifiers, key)));
+        self.capturing = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcut(modifiers: Modifiers, key: Key) -> Option<KeyboardShortcut> {
+        Some(KeyboardShortcut::new(modifiers, key))
+    }
+
+    fn overrides(entries: &[(&str, &str)]) -> Overrides {
+        entries
+            .iter()
+            .map(|(id, text)| (id.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(
+            parse("Ctrl+Shift+P").unwrap(),
+            shortcut(Modifiers::CTRL | Modifiers::SHIFT, Key::P)
+        );
+        assert_eq!(
+            parse(" control + alt + Enter ").unwrap(),
+            shortcut(Modifiers::CTRL | Modifiers::ALT, Key::Enter)
+        );
+        assert_eq!(
+            parse("Ctrl++").unwrap(),
+            shortcut(Modifiers::CTRL, Key::Plus)
+        );
+        assert_eq!(parse("F5").unwrap(), shortcut(Modifiers::NONE, Key::F5));
+    }
+
+    #[test]
+    fn empty_and_none_unbind() {
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(parse("  ").unwrap(), None);
+        assert_eq!(parse("None").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_unknown_modifiers_and_keys() {
+        assert!(parse("Hyper+P").unwrap_err().contains("Hyper"));
+        assert!(parse("Ctrl+Nope").unwrap_err().contains("Nope"));
+        assert!(
+            parse_binding("sidebar: Ctrl+P")
+                .unwrap_err()
+                .contains("sidebar")
+        );
+    }
+
+    #[test]
+    fn parses_scope_prefixes() {
+        assert_eq!(
+            parse_binding("prompt: Ctrl+Enter").unwrap(),
+            (shortcut(Modifiers::CTRL, Key::Enter), Some(Scope::Prompt))
+        );
+        assert_eq!(
+            parse_binding("Response:").unwrap(),
+            (None, Some(Scope::Response))
+        );
+        assert_eq!(
+            parse_binding("Ctrl+L").unwrap(),
+            (shortcut(Modifiers::CTRL, Key::L), None)
+        );
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let (keymap, errors) = Keymap::new(&Overrides::new());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap, Keymap::default());
+        assert!(keymap.overrides().is_empty());
+    }
+
+    #[test]
+    fn empty_override_unbinds_the_command() {
+        let (keymap, errors) = Keymap::new(&overrides(&[("record", "")]));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap.get(Command::Record), None);
+        assert_eq!(keymap.overrides(), overrides(&[("record", "")]));
+    }
+
+    #[test]
+    fn conflicts_are_per_scope() {
+        // Same keys as Ctrl+L (clear), but only while the response has focus
+        let (keymap, errors) = Keymap::new(&overrides(&[("copy", "response: Ctrl+L")]));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(keymap.conflicting(Command::Copy).is_empty());
+
+        let (keymap, errors) = Keymap::new(&overrides(&[("copy", "Ctrl+L")]));
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(keymap.conflicting(Command::Copy), [Command::Clear]);
+        assert_eq!(keymap.conflicts(), [vec![Command::Clear, Command::Copy]]);
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_and_skipped() {
+        let (keymap, errors) = Keymap::new(&overrides(&[
+            ("nonsense", "Ctrl+Q"),
+            ("clear", "Ctrl+Nope"),
+        ]));
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert_eq!(keymap, Keymap::default());
+    }
+
+    #[test]
+    fn overrides_round_trip() {
+        let entries = overrides(&[
+            ("clear", "Ctrl+Shift+L"),
+            ("copy", "response: Ctrl+C"),
+            ("record", ""),
+        ]);
+        let (keymap, errors) = Keymap::new(&entries);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap.overrides(), entries);
+    }
+}
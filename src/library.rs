@@ -0,0 +1,270 @@
+//! Task and role Markdown files under `~/.gia/<subdir>`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const TASKS: &str = "tasks";
+pub const ROLES: &str = "roles";
+
+pub fn md_dir(subdir: &str) -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".gia").join(subdir))
+}
+
+/// Names of all `.md` files below `~/.gia/<subdir>`. Files in subfolders are
+/// named `group/name`.
+pub fn load_md_files(subdir: &str) -> Vec<String> {
+    let mut files = Vec::new();
+
+    if let Some(path) = md_dir(subdir) {
+        collect_md_files(&path, "", &mut files);
+    }
+
+    files.sort();
+    files
+}
+
+fn collect_md_files(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let Some(file_name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                collect_md_files(&entry.path(), &format!("{}{}/", prefix, file_name), files);
+            } else if file_type.is_file() && file_name.ends_with(".md") {
+                let name = file_name.trim_end_matches(".md");
+                files.push(format!("{}{}", prefix, name));
+            }
+        }
+    }
+}
+
+pub fn md_file_path(subdir: &str, name: &str) -> Option<PathBuf> {
+    md_dir(subdir).map(|dir| dir.join(format!("{}.md", name)))
+}
+
+pub fn load_md_contents(subdir: &str, names: &[String]) -> HashMap<String, String> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let content = fs::read_to_string(md_file_path(subdir, name)?).ok()?;
+            Some((name.clone(), content))
+        })
+        .collect()
+}
+
+/// Optional metadata at the top of a task or role file, either as YAML between
+/// `---` lines or as TOML between `+++` lines.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FrontMatter {
+    pub description: Option<String>,
+    /// Model to select when the task is picked.
+    pub model: Option<String>,
+    /// Role to select when the task is picked.
+    pub role: Option<String>,
+    pub tags: Vec<String>,
+    /// Files attached as `-f`/`-i` options when the task is picked.
+    pub attachments: Vec<String>,
+    pub tts: Option<bool>,
+    pub tts_language: Option<String>,
+}
+
+/// Splits `content` into its front matter (if any) and the Markdown body.
+pub fn parse_front_matter(content: &str) -> (Option<FrontMatter>, &str) {
+    for delimiter in ["---", "+++"] {
+        let Some(rest) = content.strip_prefix(delimiter).and_then(|rest| {
+            rest.strip_prefix("\r\n")
+                .or_else(|| rest.strip_prefix('\n'))
+        }) else {
+            continue;
+        };
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == delimiter {
+                let header = &rest[..offset];
+                let body = &rest[offset + line.len()..];
+                let front_matter = if delimiter == "---" {
+                    serde_yaml::from_str(header).ok()
+                } else {
+                    toml::from_str(header).ok()
+                };
+                return (front_matter, body);
+            }
+            offset += line.len();
+        }
+    }
+    (None, content)
+}
+
+pub fn load_front_matter(contents: &HashMap<String, String>) -> HashMap<String, FrontMatter> {
+    contents
+        .iter()
+        .filter_map(|(name, content)| Some((name.clone(), parse_front_matter(content).0?)))
+        .collect()
+}
+
+pub fn modified(subdir: &str, name: &str) -> Option<SystemTime> {
+    fs::metadata(md_file_path(subdir, name)?)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name must not be empty".to_string());
+    }
+    if name != name.trim() {
+        return Err("Name must not start or end with whitespace".to_string());
+    }
+    // Subfolders are allowed as `group/name`, but every part must be a plain name
+    let valid = name.split('/').all(|part| {
+        !part.is_empty()
+            && part == part.trim()
+            && !part.starts_with('.')
+            && !part.contains(['\\', ':'])
+    });
+    if !valid {
+        return Err(format!("Invalid name '{}'", name));
+    }
+    Ok(())
+}
+
+fn existing_path(subdir: &str, name: &str) -> Result<PathBuf, String> {
+    let path = md_file_path(subdir, name).ok_or("No home directory available")?;
+    if !path.is_file() {
+        return Err(format!("'{}' does not exist", name));
+    }
+    Ok(path)
+}
+
+fn new_path(subdir: &str, name: &str) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    let path = md_file_path(subdir, name).ok_or("No home directory available")?;
+    if path.exists() {
+        return Err(format!("'{}' already exists", name));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    Ok(path)
+}
+
+pub fn create(subdir: &str, name: &str, content: &str) -> Result<(), String> {
+    let path = new_path(subdir, name)?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub fn rename(subdir: &str, old_name: &str, new_name: &str) -> Result<(), String> {
+    let old_path = existing_path(subdir, old_name)?;
+    let new_path = new_path(subdir, new_name)?;
+    fs::rename(&old_path, &new_path)
+        .map_err(|e| format!("Failed to rename {}: {}", old_path.display(), e))
+}
+
+pub fn delete(subdir: &str, name: &str) -> Result<(), String> {
+    let path = existing_path(subdir, name)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+}
+
+pub enum SaveError {
+    /// The file was modified on disk since it was loaded.
+    Conflict,
+    /// The file was deleted on disk since it was loaded.
+    Deleted,
+    Io(String),
+}
+
+/// Writes `content` unless the file changed on disk since `loaded_at`.
+/// Returns the new modification time.
+pub fn save(
+    subdir: &str,
+    name: &str,
+    content: &str,
+    loaded_at: Option<SystemTime>,
+) -> Result<Option<SystemTime>, SaveError> {
+    let path =
+        md_file_path(subdir, name).ok_or(SaveError::Io("No home directory available".into()))?;
+    if !path.is_file() {
+        return Err(SaveError::Deleted);
+    }
+    if loaded_at.is_some() && modified(subdir, name) != loaded_at {
+        return Err(SaveError::Conflict);
+    }
+    fs::write(&path, content)
+        .map_err(|e| SaveError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(modified(subdir, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_front_matter_is_split_from_the_body() {
+        let content =
+            "---\ndescription: Review code\ntags: [rust, review]\ntts: true\n---\n# Body\n";
+        let (front_matter, body) = parse_front_matter(content);
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.description.as_deref(), Some("Review code"));
+        assert_eq!(front_matter.tags, ["rust", "review"]);
+        assert_eq!(front_matter.tts, Some(true));
+        assert_eq!(body, "# Body\n");
+    }
+
+    #[test]
+    fn toml_front_matter_is_split_from_the_body() {
+        let content = "+++\r\nmodel = \"gemini-2.5-flash\"\r\n+++\r\nBody";
+        let (front_matter, body) = parse_front_matter(content);
+        assert_eq!(
+            front_matter.unwrap().model.as_deref(),
+            Some("gemini-2.5-flash")
+        );
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn empty_front_matter_is_stripped() {
+        let (front_matter, body) = parse_front_matter("---\n---\nBody");
+        assert!(front_matter.is_some());
+        assert_eq!(body, "Body");
+        let (front_matter, body) = parse_front_matter("+++\n+++\nBody");
+        assert!(front_matter.is_some());
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn separator_in_the_body_is_not_front_matter() {
+        let content = "Intro\n---\nmore\n---\n";
+        let (front_matter, body) = parse_front_matter(content);
+        assert!(front_matter.is_none());
+        assert_eq!(body, content);
+    }
+
+    #[test]
+    fn unterminated_front_matter_is_kept_as_body() {
+        let content = "---\ndescription: never closed\nBody";
+        let (front_matter, body) = parse_front_matter(content);
+        assert!(front_matter.is_none());
+        assert_eq!(body, content);
+    }
+
+    #[test]
+    fn invalid_front_matter_is_ignored_but_stripped() {
+        let (front_matter, body) = parse_front_matter("---\ntags: [unclosed\n---\nBody");
+        assert!(front_matter.is_none());
+        assert_eq!(body, "Body");
+    }
+}
@@ -1,12 +1,91 @@
 use arboard::Clipboard;
 use eframe::egui;
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+mod api;
+mod appearance;
+mod cli;
+mod commands;
+mod compare;
+mod config;
+mod conversations;
+mod diff;
+mod editor;
+mod export;
+mod fuzzy;
+mod gia;
+mod history;
+mod instance;
+mod keymap;
+mod library;
+mod manager;
+mod markdown;
+mod postprocess;
+mod presets;
+mod settings;
+mod template;
+mod watcher;
+mod workspace;
+
+use commands::{Command, PaletteEntry};
+use gia::{GiaRequest, GiaResult};
+use library::FrontMatter;
+use presets::Preset;
+use workspace::Workspace;
+
 fn main() -> eframe::Result<()> {
+    let mut cli_args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli_args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if let Some(piped) = cli::read_piped_stdin() {
+        cli_args.prompt = Some(match cli_args.prompt.take() {
+            Some(prompt) => format!("{}\n\n{}", prompt, piped),
+            None => piped,
+        });
+    }
+
+    let listener = if cli_args.new_instance {
+        None
+    } else {
+        match instance::claim() {
+            Ok(Some(listener)) => Some(listener),
+            Ok(None) => {
+                // The running window decides whether it serves the API
+                if cli_args.api_port.is_some() {
+                    eprintln!(
+                        "giagui is already running, so --api-port has no effect; \
+                         pass --new-instance to open a separate window with the API"
+                    );
+                    std::process::exit(1);
+                }
+                let mut forwarded = cli_args.clone();
+                forwarded.make_paths_absolute();
+                if let Err(e) = instance::forward(&forwarded) {
+                    eprintln!("{}; pass --new-instance to open a separate window", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        }
+    };
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
@@ -17,7 +96,26 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "GIA GUI",
         options,
-        Box::new(|_cc| Ok(Box::new(GiaApp::default()))),
+        Box::new(|cc| {
+            let mut app = GiaApp::default();
+            app.apply_appearance(&cc.egui_ctx);
+            app.watch_library(&cc.egui_ctx);
+            if let Some(listener) = listener {
+                instance::serve(
+                    listener,
+                    Arc::clone(&app.forwarded_requests),
+                    cc.egui_ctx.clone(),
+                );
+            }
+            if let Ok(current_dir) = std::env::current_dir() {
+                app.load_workspace(&current_dir);
+            }
+            if let Some(port) = cli_args.api_port {
+                app.start_api(port, &cc.egui_ctx);
+            }
+            app.apply_cli_args(cli_args);
+            Ok(Box::new(app))
+        }),
     )
 }
 
@@ -35,6 +133,10 @@ fn load_icon() -> egui::IconData {
     }
 }
 
+/// Ids of the text fields that have their own shortcut scope.
+const PROMPT_EDITOR: &str = "prompt_editor";
+const RESPONSE_VIEW: &str = "response_view";
+
 struct GiaApp {
     prompt: String,
     options: String,
@@ -46,87 +148,325 @@ struct GiaApp {
     model: String,
     task: String,
     role: String,
+    /// Further tasks and roles after `task`/`role`, in the order they are combined.
+    extra_tasks: Vec<String>,
+    extra_roles: Vec<String>,
     tasks: Vec<String>,
     roles: Vec<String>,
+    task_contents: HashMap<String, String>,
+    role_contents: HashMap<String, String>,
+    task_meta: HashMap<String, FrontMatter>,
+    role_meta: HashMap<String, FrontMatter>,
+    task_tag: String,
+    show_preview: bool,
+    task_picker: fuzzy::Picker,
+    role_picker: fuzzy::Picker,
+    palette: fuzzy::Picker,
+    keymap: keymap::Keymap,
+    settings_window: settings::Window,
+    /// Whether the picker adds to the selection instead of replacing it.
+    task_picker_adds: bool,
+    role_picker_adds: bool,
+    manager: manager::Manager,
+    library_watcher: Option<watcher::LibraryWatcher>,
     is_executing: Arc<Mutex<bool>>,
     animation_time: f64,
-    pending_response: Arc<Mutex<Option<String>>>,
+    /// Finished requests, picked up in `update`.
+    pending_responses: Arc<Mutex<Vec<(Origin, GiaResult)>>>,
+    /// Responses of this session; regenerating adds variants to the last turn.
+    history: history::History,
+    /// The conversation as it was before the turn with this id was sent, so
+    /// regenerating it does not continue after its first answer.
+    turn_snapshot: Option<(u64, conversations::Snapshot)>,
+    diff_view: diff::DiffView,
+    export_dialog: export::Dialog,
+    editor_events: editor::Events,
+    /// Fields currently open in an external editor.
+    editing: Vec<editor::Session>,
+    /// Finished post-processing runs, picked up in `update`.
+    action_results: postprocess::Pending,
+    /// Model to regenerate with; empty keeps the model of the shown variant.
+    regenerate_model: String,
     tts_enabled: bool,
     tts_language: String,
+    conversations: Vec<conversations::Conversation>,
+    conversation_names: conversations::Names,
+    /// Conversation resumed with `-R`; `None` means the most recent one.
+    active_conversation: Option<String>,
+    show_conversations_window: bool,
+    /// Why the conversation browser cannot be used with the installed gia.
+    conversations_unavailable: Option<String>,
+    /// Conversation being renamed and the edited name.
+    conversation_rename: Option<(String, String)>,
+    settings: config::Settings,
+    /// Post-send reset of the current preset, or the default from the settings.
+    reset_policy: config::ResetPolicy,
+    presets: Vec<Preset>,
+    selected_preset: String,
+    preset_name: String,
+    preset_path: String,
+    show_presets_window: bool,
+    status: String,
+    template_form: Option<TemplateForm>,
+    template_values: template::StoredValues,
+    response_selection: String,
+    workspace: Option<Workspace>,
+    working_dir: Option<PathBuf>,
+    title_dirty: bool,
+    forwarded_requests: instance::RequestQueue,
+    api: Option<api::ApiServer>,
+    compare: compare::Compare,
+}
+
+/// What a request was started for, so its result is filed with the right turn.
+enum Origin {
+    /// A new turn, with the conversation it continues as it was before.
+    Send {
+        resumed: Option<conversations::Snapshot>,
+    },
+    /// Another variant of the turn with this id, sent against `branch`, a copy
+    /// of the conversation from before the turn.
+    Regenerate { turn: u64, branch: Option<String> },
+}
+
+/// Whether `request` continues a stored conversation.
+fn resumes(request: &GiaRequest) -> bool {
+    request
+        .args
+        .iter()
+        .any(|arg| arg == "-R" || arg.starts_with("--resume"))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SendMode {
+    Send,
+    Record,
+    /// Run against the models selected in the compare window.
+    Compare,
+}
+
+/// Variables to fill in before a send that was started with placeholders.
+struct TemplateForm {
+    fields: Vec<template::Field>,
+    mode: SendMode,
+    focused: bool,
 }
 
 impl Default for GiaApp {
     fn default() -> Self {
-        let tasks = load_md_files("tasks");
-        let roles = load_md_files("roles");
+        let tasks = library::load_md_files(library::TASKS);
+        let roles = library::load_md_files(library::ROLES);
+        let task_contents = library::load_md_contents(library::TASKS, &tasks);
+        let role_contents = library::load_md_contents(library::ROLES, &roles);
+        let (settings, settings_error) = config::load_settings();
+        let (keymap, keymap_errors) = keymap::Keymap::new(&settings.shortcuts);
+        let preset_path = dirs::home_dir()
+            .map(|home| home.join("giagui-presets.toml").display().to_string())
+            .unwrap_or_default();
 
         Self {
             prompt: String::new(),
             options: String::new(),
-            use_clipboard: false,
-            browser_output: false,
-            resume: false,
+            use_clipboard: settings.general.use_clipboard,
+            browser_output: settings.general.browser_output,
+            resume: settings.general.resume,
             response: String::new(),
             first_frame: true,
-            model: "gemini-2.5-flash-lite".to_string(),
+            model: settings.general.model.clone(),
             task: String::new(),
             role: String::new(),
+            extra_tasks: Vec::new(),
+            extra_roles: Vec::new(),
+            task_meta: library::load_front_matter(&task_contents),
+            role_meta: library::load_front_matter(&role_contents),
+            task_tag: String::new(),
+            task_contents,
+            role_contents,
             tasks,
             roles,
+            show_preview: settings.appearance.show_preview,
+            task_picker: fuzzy::Picker::default(),
+            role_picker: fuzzy::Picker::default(),
+            palette: fuzzy::Picker::default(),
+            keymap,
+            settings_window: settings::Window::default(),
+            task_picker_adds: false,
+            role_picker_adds: false,
+            manager: manager::Manager::default(),
+            library_watcher: None,
             is_executing: Arc::new(Mutex::new(false)),
             animation_time: 0.0,
-            pending_response: Arc::new(Mutex::new(None)),
-            tts_enabled: false,
-            tts_language: "de-DE".to_string(),
+            pending_responses: Arc::default(),
+            history: history::History::default(),
+            turn_snapshot: None,
+            diff_view: diff::DiffView::default(),
+            export_dialog: export::Dialog {
+                settings: settings.export.clone(),
+                ..Default::default()
+            },
+            editor_events: Arc::default(),
+            editing: Vec::new(),
+            action_results: Arc::default(),
+            regenerate_model: String::new(),
+            tts_enabled: settings.general.tts_enabled,
+            tts_language: settings.general.tts_language.clone(),
+            conversations: conversations::list(),
+            conversation_names: conversations::load_names(),
+            active_conversation: None,
+            show_conversations_window: false,
+            conversations_unavailable: None,
+            conversation_rename: None,
+            reset_policy: settings.reset.clone(),
+            settings,
+            presets: presets::load_presets(),
+            selected_preset: String::new(),
+            preset_name: String::new(),
+            preset_path,
+            show_presets_window: false,
+            status: settings_error
+                .into_iter()
+                .chain(keymap_errors)
+                .collect::<Vec<_>>()
+                .join("; "),
+            template_form: None,
+            template_values: template::load_values(),
+            response_selection: String::new(),
+            workspace: None,
+            working_dir: None,
+            title_dirty: false,
+            forwarded_requests: Arc::default(),
+            api: None,
+            compare: compare::Compare::default(),
         }
     }
 }
 
-fn load_md_files(subdir: &str) -> Vec<String> {
-    let mut files = Vec::new();
-
-    if let Some(home_dir) = dirs::home_dir() {
-        let path = home_dir.join(".gia").join(subdir);
-
-        if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_file() {
-                        if let Some(file_name) = entry.file_name().to_str() {
-                            if file_name.ends_with(".md") {
-                                let name = file_name.trim_end_matches(".md").to_string();
-                                files.push(name);
-                            }
-                        }
-                    }
+/// Lists `names` below `prefix`, with subfolders as collapsible groups.
+fn md_tree_ui(
+    ui: &mut egui::Ui,
+    names: &[String],
+    prefix: &str,
+    selected: &mut String,
+    contents: &HashMap<String, String>,
+    meta: &HashMap<String, FrontMatter>,
+) -> bool {
+    let mut changed = false;
+    let mut groups: Vec<&str> = Vec::new();
+    let mut leaves = Vec::new();
+    for name in names {
+        let Some(rest) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        match rest.split_once('/') {
+            Some((group, _)) => {
+                if !groups.contains(&group) {
+                    groups.push(group);
                 }
             }
+            None => leaves.push((name, rest)),
         }
     }
 
-    files.sort();
-    files
+    for group in groups {
+        let group_prefix = format!("{}{}/", prefix, group);
+        egui::CollapsingHeader::new(group)
+            .id_salt(&group_prefix)
+            .default_open(selected.starts_with(&group_prefix))
+            .show(ui, |ui| {
+                changed |= md_tree_ui(ui, names, &group_prefix, selected, contents, meta);
+            });
+    }
+    for (name, label) in leaves {
+        let response = ui
+            .selectable_value(selected, name.clone(), label)
+            .on_hover_ui(|ui| md_hover_ui(ui, contents.get(name), meta.get(name)));
+        changed |= response.changed();
+    }
+    changed
+}
+
+/// Lists the additional selections below a selector, with buttons to reorder
+/// them (moving the first one up swaps it with `primary`) and to remove them.
+fn extra_selection_ui(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    primary: &mut String,
+    extras: &mut Vec<String>,
+) {
+    let mut move_up = None;
+    let mut move_down = None;
+    let mut remove = None;
+
+    ui.push_id(id_salt, |ui| {
+        for (index, name) in extras.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("+ {}", name));
+                if ui.small_button("⬆").clicked() {
+                    move_up = Some(index);
+                }
+                if ui
+                    .add_enabled(index + 1 < extras.len(), egui::Button::new("⬇").small())
+                    .clicked()
+                {
+                    move_down = Some(index);
+                }
+                if ui.small_button("✖").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+    });
+
+    if let Some(index) = move_up {
+        if index == 0 {
+            std::mem::swap(primary, &mut extras[0]);
+        } else {
+            extras.swap(index - 1, index);
+        }
+    }
+    if let Some(index) = move_down {
+        extras.swap(index, index + 1);
+    }
+    if let Some(index) = remove {
+        extras.remove(index);
+    }
 }
 
-fn is_media_file(path: &Path) -> bool {
-    const MEDIA_EXTENSIONS: &[&str] = &[
-        "jpg", "jpeg", "png", "webp", "heic", "pdf", "ogg", "opus", "mp3", "m4a", "mp4",
-    ];
+/// Adds `name` to a selection, as the primary one if nothing is selected yet.
+fn add_selection(primary: &mut String, extras: &mut Vec<String>, name: String) {
+    if primary.is_empty() {
+        *primary = name;
+    } else if *primary != name && !extras.contains(&name) {
+        extras.push(name);
+    }
+}
 
-    if let Some(ext) = path.extension() {
-        if let Some(ext_str) = ext.to_str() {
-            return MEDIA_EXTENSIONS.contains(&ext_str.to_lowercase().as_str());
+fn md_hover_ui(ui: &mut egui::Ui, content: Option<&String>, meta: Option<&FrontMatter>) {
+    if let Some(meta) = meta {
+        if let Some(description) = &meta.description {
+            ui.strong(description);
         }
+        if !meta.tags.is_empty() {
+            ui.weak(format!("Tags: {}", meta.tags.join(", ")));
+        }
+        ui.separator();
+    }
+    if let Some(content) = content {
+        markdown::show_excerpt(ui, content, 30);
     }
-    false
 }
 
-fn collect_files_recursive(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+fn collect_files_recursive(dir: &Path, options: &config::Attachments, files: &mut Vec<PathBuf>) {
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
+            if options.skip_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
             if path.is_dir() {
-                collect_files_recursive(&path, files);
+                if options.recurse_directories {
+                    collect_files_recursive(&path, options, files);
+                }
             } else if path.is_file() {
                 files.push(path);
             }
@@ -136,65 +476,127 @@ fn collect_files_recursive(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
 
 impl eframe::App for GiaApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for pending response
-        if let Ok(mut pending) = self.pending_response.lock() {
-            if let Some(response) = pending.take() {
-                self.response = response;
+        // Check for finished requests
+        let finished = std::mem::take(&mut *self.pending_responses.lock().unwrap());
+        for (origin, result) in finished {
+            if let Some(api) = &self.api {
+                api.publish_result(&result);
+            }
+            let started_conversation = !resumes(&result.request);
+            match origin {
+                Origin::Send { resumed } => {
+                    let turn = self.history.push(result);
+                    self.history.truncate(self.settings.history.max_turns);
+                    self.turn_snapshot = resumed.map(|snapshot| (turn, snapshot));
+                }
+                Origin::Regenerate { turn, branch } => {
+                    match self.history.turn_mut(turn) {
+                        Some(turn) => turn.push(result),
+                        None => {
+                            self.history.push(result);
+                            self.history.truncate(self.settings.history.max_turns);
+                        }
+                    }
+                    // Continue from the variant now shown
+                    if branch.is_some() {
+                        self.active_conversation = branch;
+                    }
+                }
+            }
+            self.show_variant();
+
+            self.conversations = conversations::list();
+            if started_conversation {
+                self.active_conversation = self.conversations.first().map(|c| c.id.clone());
             }
         }
 
+        if self.title_dirty {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(self.window_title()));
+            self.title_dirty = false;
+        }
+
         // Request repaint for animation
         let is_exec = *self.is_executing.lock().unwrap();
+
+        self.handle_api_commands();
+        self.handle_editor_events();
+        self.handle_action_result();
+
+        // Requests forwarded from other launches wait until the current run is done
+        if !is_exec {
+            let next = self.forwarded_requests.lock().unwrap().pop_front();
+            if let Some(args) = next {
+                self.apply_cli_args(args);
+            }
+        }
+
         if is_exec {
             self.animation_time += ctx.input(|i| i.stable_dt as f64);
             ctx.request_repaint();
         }
 
-        // Handle keyboard shortcuts
-        if ctx.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.ctrl) {
-            self.send_prompt();
-        }
-        if ctx.input(|i| i.key_pressed(egui::Key::R) && i.modifiers.ctrl) {
-            self.send_prompt_with_audio();
-        }
-        if ctx.input(|i| i.key_pressed(egui::Key::L) && i.modifiers.ctrl) {
-            self.clear_form();
-        }
-        if ctx.input(|i| i.key_pressed(egui::Key::C) && i.modifiers.ctrl && i.modifiers.shift) {
-            self.copy_response();
-        }
-        if ctx.input(|i| i.key_pressed(egui::Key::O) && i.modifiers.ctrl) {
-            self.show_conversation();
-        }
-        if ctx.input(|i| i.key_pressed(egui::Key::F1)) {
-            self.show_help();
-        }
-        // Checkbox shortcuts
-        if ctx.input(|i| i.key_pressed(egui::Key::Num1) && i.modifiers.ctrl) {
-            self.use_clipboard = !self.use_clipboard;
+        // Handle keyboard shortcuts before the text fields see the keys,
+        // unless one is being recorded
+        if !self.settings_window.is_capturing() {
+            let focused = ctx.memory(|m| m.focused());
+            let focus = if focused == Some(egui::Id::new(PROMPT_EDITOR)) {
+                commands::Scope::Prompt
+            } else if focused == Some(egui::Id::new(RESPONSE_VIEW)) {
+                commands::Scope::Response
+            } else {
+                commands::Scope::Global
+            };
+            let typing = ctx.wants_keyboard_input();
+            let pressed = ctx.input_mut(|i| self.keymap.take_pressed(i, focus, typing));
+            for command in pressed {
+                self.execute_command(command, ctx);
+            }
         }
-        if ctx.input(|i| i.key_pressed(egui::Key::Num2) && i.modifiers.ctrl) {
-            self.browser_output = !self.browser_output;
+
+        self.command_palette(ctx);
+        self.settings_window(ctx);
+        self.presets_window(ctx);
+        self.conversations_window(ctx);
+        self.diff_window(ctx);
+        self.export_window(ctx);
+        if self.compare.show(ctx, &self.settings.models) {
+            self.prepare_send(SendMode::Compare);
         }
-        if ctx.input(|i| i.key_pressed(egui::Key::Num3) && i.modifiers.ctrl) {
-            self.resume = !self.resume;
+
+        if self
+            .library_watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.take_changed())
+        {
+            self.reload_library();
         }
-        if ctx.input(|i| i.key_pressed(egui::Key::Num4) && i.modifiers.ctrl) {
-            self.tts_enabled = !self.tts_enabled;
+
+        self.preview_panel(ctx);
+        self.template_form_window(ctx);
+        self.md_pickers(ctx);
+        if let Some(change) = self.manager.show(ctx, &self.tasks, &self.roles) {
+            self.apply_library_change(change);
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical(|ui| {
                 // Prompt input
                 ui.vertical(|ui| {
-                    ui.label("Prompt:");
+                    ui.horizontal(|ui| {
+                        ui.label("Prompt:");
+                        self.editor_button(ui, editor::Target::Prompt, Command::EditPrompt);
+                    });
+                    let rows = self.settings.appearance.prompt_rows.max(1);
+                    let row_height = ui.text_style_height(&egui::TextStyle::Body);
                     let prompt_response = egui::ScrollArea::vertical()
-                        .max_height(60.0)
+                        .max_height(rows as f32 * row_height + 8.0)
                         .show(ui, |ui| {
                             ui.add(
                                 egui::TextEdit::multiline(&mut self.prompt)
+                                    .id(egui::Id::new(PROMPT_EDITOR))
                                     .desired_width(f32::INFINITY)
-                                    .desired_rows(3),
+                                    .desired_rows(rows),
                             )
                         })
                         .inner;
@@ -212,42 +614,77 @@ impl eframe::App for GiaApp {
                     for file in dropped_files {
                         if let Some(path) = file.path {
                             if path.is_dir() {
-                                // Recursively add all files from directory
-                                let mut files_to_add = Vec::new();
-                                collect_files_recursive(&path, &mut files_to_add);
-
-                                for file_path in files_to_add {
-                                    if let Some(path_str) = file_path.to_str() {
-                                        let option_line = if is_media_file(&file_path) {
-                                            format!("-i{}", path_str)
-                                        } else {
-                                            format!("-f{}", path_str)
-                                        };
+                                self.load_workspace(&path);
+                            }
+                            self.attach_path(&path);
+                        }
+                    }
+                }
 
-                                        if !self.options.is_empty() && !self.options.ends_with('\n')
-                                        {
-                                            self.options.push('\n');
-                                        }
-                                        self.options.push_str(&option_line);
+                ui.add_space(5.0);
+
+                // Preset selector
+                ui.horizontal(|ui| {
+                    ui.label("Preset:");
+                    let mut apply = None;
+                    egui::ComboBox::from_id_salt("preset_selector")
+                        .selected_text(if self.selected_preset.is_empty() {
+                            "Select Preset"
+                        } else {
+                            &self.selected_preset
+                        })
+                        .show_ui(ui, |ui| {
+                            for (index, preset) in self.presets.iter().enumerate() {
+                                let label = match u8::try_from(index + 1) {
+                                    Ok(n) if n <= 9 => {
+                                        self.with_shortcut_tag(&preset.name, Command::Preset(n))
                                     }
-                                }
-                            } else if let Some(path_str) = path.to_str() {
-                                let option_line = if is_media_file(&path) {
-                                    format!("-i{}", path_str)
-                                } else {
-                                    format!("-f{}", path_str)
+                                    _ => preset.name.clone(),
                                 };
-
-                                if !self.options.is_empty() && !self.options.ends_with('\n') {
-                                    self.options.push('\n');
+                                if ui
+                                    .selectable_label(self.selected_preset == preset.name, label)
+                                    .clicked()
+                                {
+                                    apply = Some(index);
                                 }
-                                self.options.push_str(&option_line);
                             }
-                        }
+                        });
+                    if let Some(index) = apply {
+                        self.apply_preset(index);
                     }
-                }
 
-                ui.add_space(10.0);
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.preset_name)
+                            .hint_text("Preset name")
+                            .desired_width(120.0),
+                    );
+                    if ui.button("Save").clicked() {
+                        self.save_preset();
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.selected_preset.is_empty(),
+                            egui::Button::new("Update"),
+                        )
+                        .clicked()
+                    {
+                        self.update_preset();
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.selected_preset.is_empty(),
+                            egui::Button::new("Delete"),
+                        )
+                        .clicked()
+                    {
+                        self.delete_preset();
+                    }
+                    if ui.button("Import/Export...").clicked() {
+                        self.execute_command(Command::Presets, ui.ctx());
+                    }
+                });
+
+                ui.add_space(5.0);
 
                 // Options group and custom options side by side
                 ui.horizontal(|ui| {
@@ -255,13 +692,28 @@ impl eframe::App for GiaApp {
                     ui.group(|ui| {
                         ui.vertical(|ui| {
                             ui.label("Options");
-                            ui.checkbox(&mut self.use_clipboard, "Use clipboard input (-c) [Ctrl+1]");
-                            ui.checkbox(
-                                &mut self.browser_output,
-                                "Browser output (--browser-output) [Ctrl+2]",
-                            );
-                            ui.checkbox(&mut self.resume, "Resume last conversation (-R) [Ctrl+3]");
-                            ui.checkbox(&mut self.tts_enabled, "Text-to-Speech (--tts) [Ctrl+4]");
+                            let labels = [
+                                self.with_shortcut_tag(
+                                    "Use clipboard input (-c)",
+                                    Command::ToggleClipboard,
+                                ),
+                                self.with_shortcut_tag(
+                                    "Browser output (--browser-output)",
+                                    Command::ToggleBrowserOutput,
+                                ),
+                                self.with_shortcut_tag(
+                                    "Resume last conversation (-R)",
+                                    Command::ToggleResume,
+                                ),
+                                self.with_shortcut_tag(
+                                    "Text-to-Speech (--tts)",
+                                    Command::ToggleTts,
+                                ),
+                            ];
+                            ui.checkbox(&mut self.use_clipboard, &labels[0]);
+                            ui.checkbox(&mut self.browser_output, &labels[1]);
+                            ui.checkbox(&mut self.resume, &labels[2]);
+                            ui.checkbox(&mut self.tts_enabled, &labels[3]);
                         });
                     });
 
@@ -306,76 +758,170 @@ impl eframe::App for GiaApp {
                                 egui::ComboBox::from_id_salt("model_selector")
                                     .selected_text(&self.model)
                                     .show_ui(ui, |ui| {
-                                        ui.selectable_value(
-                                            &mut self.model,
-                                            "gemini-2.5-pro".to_string(),
-                                            "Gemini 2.5 Pro",
-                                        );
-                                        ui.selectable_value(
-                                            &mut self.model,
-                                            "gemini-2.5-flash".to_string(),
-                                            "Gemini 2.5 Flash",
-                                        );
-                                        ui.selectable_value(
-                                            &mut self.model,
-                                            "gemini-2.5-flash-lite".to_string(),
-                                            "Gemini 2.5 Flash-Lite",
-                                        );
-                                        ui.selectable_value(
-                                            &mut self.model,
-                                            "gemini-2.0-flash".to_string(),
-                                            "Gemini 2.0 Flash",
-                                        );
-                                        ui.selectable_value(
-                                            &mut self.model,
-                                            "gemini-2.0-flash-lite".to_string(),
-                                            "Gemini 2.0 Flash-Lite",
-                                        );
+                                        for model in &self.settings.models {
+                                            ui.selectable_value(
+                                                &mut self.model,
+                                                model.name.clone(),
+                                                &model.label,
+                                            );
+                                        }
                                     });
 
                                 egui::ComboBox::from_id_salt("tts_language_selector")
                                     .selected_text(&self.tts_language)
                                     .show_ui(ui, |ui| {
-                                        ui.selectable_value(
-                                            &mut self.tts_language,
-                                            "de-DE".to_string(),
-                                            "de-DE",
-                                        );
-                                        ui.selectable_value(
-                                            &mut self.tts_language,
-                                            "en-US".to_string(),
-                                            "en-US",
-                                        );
+                                        for language in &self.settings.general.tts_languages {
+                                            ui.selectable_value(
+                                                &mut self.tts_language,
+                                                language.clone(),
+                                                language,
+                                            );
+                                        }
                                     });
                             });
 
                             // Right column: Task and Role
                             ui.vertical(|ui| {
-                                egui::ComboBox::from_id_salt("task_selector")
-                                    .selected_text(if self.task.is_empty() {
-                                        "Select Task"
-                                    } else {
-                                        &self.task
-                                    })
-                                    .show_ui(ui, |ui| {
-                                        ui.selectable_value(&mut self.task, String::new(), "None");
-                                        for task in &self.tasks {
-                                            ui.selectable_value(&mut self.task, task.clone(), task);
-                                        }
-                                    });
+                                ui.horizontal(|ui| {
+                                    let tasks = self.filtered_tasks();
+                                    let mut task_changed = false;
+                                    egui::ComboBox::from_id_salt("task_selector")
+                                        .selected_text(if self.task.is_empty() {
+                                            "Select Task"
+                                        } else {
+                                            &self.task
+                                        })
+                                        .show_ui(ui, |ui| {
+                                            task_changed |= ui
+                                                .selectable_value(
+                                                    &mut self.task,
+                                                    String::new(),
+                                                    "None",
+                                                )
+                                                .changed();
+                                            task_changed |= md_tree_ui(
+                                                ui,
+                                                &tasks,
+                                                "",
+                                                &mut self.task,
+                                                &self.task_contents,
+                                                &self.task_meta,
+                                            );
+                                        });
+                                    if task_changed {
+                                        self.apply_task_metadata();
+                                    }
+                                    if ui
+                                        .small_button("🔍")
+                                        .on_hover_text(
+                                            self.with_shortcut(
+                                                "Search tasks",
+                                                Command::SearchTasks,
+                                            ),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.task_picker_adds = false;
+                                        self.task_picker.open();
+                                    }
+                                    if ui
+                                        .small_button("➕")
+                                        .on_hover_text("Add another task")
+                                        .clicked()
+                                    {
+                                        self.task_picker_adds = true;
+                                        self.task_picker.open();
+                                    }
 
-                                egui::ComboBox::from_id_salt("role_selector")
-                                    .selected_text(if self.role.is_empty() {
-                                        "Select Role"
-                                    } else {
-                                        &self.role
-                                    })
-                                    .show_ui(ui, |ui| {
-                                        ui.selectable_value(&mut self.role, String::new(), "None");
-                                        for role in &self.roles {
-                                            ui.selectable_value(&mut self.role, role.clone(), role);
-                                        }
-                                    });
+                                    let tags = self.task_tags();
+                                    if !tags.is_empty() {
+                                        egui::ComboBox::from_id_salt("task_tag_filter")
+                                            .width(80.0)
+                                            .selected_text(if self.task_tag.is_empty() {
+                                                "All tags"
+                                            } else {
+                                                &self.task_tag
+                                            })
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(
+                                                    &mut self.task_tag,
+                                                    String::new(),
+                                                    "All tags",
+                                                );
+                                                for tag in tags {
+                                                    ui.selectable_value(
+                                                        &mut self.task_tag,
+                                                        tag.clone(),
+                                                        tag,
+                                                    );
+                                                }
+                                            });
+                                    }
+                                });
+                                extra_selection_ui(
+                                    ui,
+                                    "extra_tasks",
+                                    &mut self.task,
+                                    &mut self.extra_tasks,
+                                );
+
+                                ui.horizontal(|ui| {
+                                    egui::ComboBox::from_id_salt("role_selector")
+                                        .selected_text(if self.role.is_empty() {
+                                            "Select Role"
+                                        } else {
+                                            &self.role
+                                        })
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                &mut self.role,
+                                                String::new(),
+                                                "None",
+                                            );
+                                            md_tree_ui(
+                                                ui,
+                                                &self.roles,
+                                                "",
+                                                &mut self.role,
+                                                &self.role_contents,
+                                                &self.role_meta,
+                                            );
+                                        });
+                                    if ui
+                                        .small_button("🔍")
+                                        .on_hover_text(
+                                            self.with_shortcut(
+                                                "Search roles",
+                                                Command::SearchRoles,
+                                            ),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.role_picker_adds = false;
+                                        self.role_picker.open();
+                                    }
+                                    if ui
+                                        .small_button("➕")
+                                        .on_hover_text("Add another role")
+                                        .clicked()
+                                    {
+                                        self.role_picker_adds = true;
+                                        self.role_picker.open();
+                                    }
+                                });
+                                extra_selection_ui(
+                                    ui,
+                                    "extra_roles",
+                                    &mut self.role,
+                                    &mut self.extra_roles,
+                                );
+
+                                ui.horizontal(|ui| {
+                                    ui.toggle_value(&mut self.show_preview, "Preview");
+                                    if ui.button("Manage...").clicked() {
+                                        self.execute_command(Command::ManageLibrary, ui.ctx());
+                                    }
+                                });
                             });
                         });
                     });
@@ -385,25 +931,27 @@ impl eframe::App for GiaApp {
 
                 // Buttons
                 ui.horizontal(|ui| {
-                    if ui.button("Send (Ctrl+Enter)").clicked() {
-                        self.send_prompt();
-                    }
-                    if ui.button("Record (Ctrl+R)").clicked() {
-                        self.send_prompt_with_audio();
-                    }
-                    if ui.button("Clear (Ctrl+L)").clicked() {
-                        self.clear_form();
-                    }
-                    if ui.button("Copy (Ctrl+Shift+C)").clicked() {
-                        self.copy_response();
-                    }
-                    if ui.button("Conversation (Ctrl+O)").clicked() {
-                        self.show_conversation();
-                    }
-                    if ui.button("Help (F1)").clicked() {
-                        self.show_help();
-                    }
+                    self.command_button(ui, "Send", Command::Send);
+                    self.command_button(ui, "Record", Command::Record);
+                    self.command_button(ui, "Clear", Command::Clear);
+                    self.command_button(ui, "Copy", Command::Copy);
+                    self.command_button(ui, "Export", Command::Export);
+                    self.command_button(ui, "Conversation", Command::ShowConversation);
+                    self.command_button(ui, "Help", Command::Help);
+                    self.command_button(ui, "Compare...", Command::Compare);
+                    self.command_button(ui, "Settings...", Command::Settings);
                 });
+                self.actions_ui(ui);
+                self.conversation_row_ui(ui);
+                self.reset_policy_ui(ui);
+
+                if !self.status.is_empty() {
+                    ui.label(&self.status);
+                }
+                let queued = self.forwarded_requests.lock().unwrap().len();
+                if queued > 0 {
+                    ui.label(format!("{} forwarded request(s) queued", queued));
+                }
 
                 ui.add_space(5.0);
 
@@ -441,13 +989,31 @@ impl eframe::App for GiaApp {
                     ui.add_space(5.0);
                 }
 
+                self.variants_ui(ui);
+                ui.horizontal(|ui| {
+                    ui.label("Response:");
+                    self.editor_button(ui, editor::Target::Response, Command::EditResponse);
+                });
+
                 // Response box - use remaining space
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.add_sized(
-                        ui.available_size(),
-                        egui::TextEdit::multiline(&mut self.response)
-                            .font(egui::TextStyle::Monospace),
-                    );
+                    let font = if self.settings.appearance.response_monospace {
+                        egui::TextStyle::Monospace
+                    } else {
+                        egui::TextStyle::Body
+                    };
+                    let output = egui::TextEdit::multiline(&mut self.response)
+                        .id(egui::Id::new(RESPONSE_VIEW))
+                        .font(font)
+                        .desired_width(f32::INFINITY)
+                        .min_size(ui.available_size())
+                        .show(ui);
+                    // Remember the selection for the {{selection}} placeholder
+                    if let Some(range) = output.cursor_range
+                        && !range.is_empty()
+                    {
+                        self.response_selection = range.slice_str(&self.response).to_string();
+                    }
                 });
             });
         });
@@ -455,16 +1021,456 @@ impl eframe::App for GiaApp {
 }
 
 impl GiaApp {
+    fn shortcut(&self, command: Command) -> Option<egui::KeyboardShortcut> {
+        self.keymap.get(command)
+    }
+
+    fn shortcut_text(&self, command: Command) -> Option<String> {
+        self.shortcut(command)
+            .map(|shortcut| commands::format_shortcut(&shortcut))
+    }
+
+    /// `text (Shortcut)`, or just `text` if the command has no shortcut.
+    fn with_shortcut(&self, text: &str, command: Command) -> String {
+        match self.shortcut_text(command) {
+            Some(shortcut) => format!("{} ({})", text, shortcut),
+            None => text.to_string(),
+        }
+    }
+
+    /// `text [Shortcut]`, for labels that already contain parentheses.
+    fn with_shortcut_tag(&self, text: &str, command: Command) -> String {
+        match self.shortcut_text(command) {
+            Some(shortcut) => format!("{} [{}]", text, shortcut),
+            None => text.to_string(),
+        }
+    }
+
+    fn command_button(&mut self, ui: &mut egui::Ui, text: &str, command: Command) {
+        if ui.button(self.with_shortcut(text, command)).clicked() {
+            self.execute_command(command, ui.ctx());
+        }
+    }
+
+    fn execute_command(&mut self, command: Command, ctx: &egui::Context) {
+        match command {
+            Command::Send => self.send_prompt(),
+            Command::Record => self.send_prompt_with_audio(),
+            Command::Clear => self.clear_form(),
+            Command::Copy => self.copy_response(),
+            Command::Export => self.export_dialog.open = true,
+            Command::ShowConversation => self.show_conversation(),
+            Command::Help => self.show_help(),
+            Command::ToggleClipboard => self.use_clipboard = !self.use_clipboard,
+            Command::ToggleBrowserOutput => self.browser_output = !self.browser_output,
+            Command::ToggleResume => self.resume = !self.resume,
+            Command::ToggleTts => self.tts_enabled = !self.tts_enabled,
+            Command::SearchTasks => {
+                self.task_picker_adds = false;
+                self.task_picker.open();
+            }
+            Command::SearchRoles => {
+                self.role_picker_adds = false;
+                self.role_picker.open();
+            }
+            Command::Regenerate => self.regenerate(),
+            Command::PreviousVariant => self.step_variant(-1),
+            Command::NextVariant => self.step_variant(1),
+            Command::Diff => self.open_diff(),
+            Command::EditPrompt => self.open_in_editor(editor::Target::Prompt, ctx),
+            Command::EditResponse => self.open_in_editor(editor::Target::Response, ctx),
+            Command::Compare => {
+                if self.compare.models.is_empty() {
+                    self.compare.models.push(self.model.clone());
+                }
+                self.compare.open = true;
+            }
+            Command::NewConversation => self.new_conversation(),
+            Command::Conversations => {
+                self.refresh_conversations();
+                self.show_conversations_window = true;
+            }
+            Command::Presets => self.show_presets_window = true,
+            Command::ManageLibrary => self.manager.open = true,
+            Command::TogglePreview => self.show_preview = !self.show_preview,
+            Command::Palette => self.palette.open(),
+            Command::Settings => self
+                .settings_window
+                .open(&self.settings, settings::Tab::General),
+            Command::Shortcuts => self
+                .settings_window
+                .open(&self.settings, settings::Tab::Shortcuts),
+            Command::ZoomIn => self.set_zoom(ctx, ctx.zoom_factor() + 0.1),
+            Command::ZoomOut => self.set_zoom(ctx, ctx.zoom_factor() - 0.1),
+            Command::ResetZoom => self.set_zoom(ctx, 1.0),
+            Command::SwitchTheme => self.switch_theme(ctx),
+            Command::Preset(n) => self.apply_preset(usize::from(n) - 1),
+        }
+    }
+
+    fn settings_window(&mut self, ctx: &egui::Context) {
+        match self.settings_window.show(ctx) {
+            Some(settings::Action::Apply(settings)) => self.apply_settings(*settings, ctx),
+            Some(settings::Action::ForgetTemplateValues) => {
+                self.template_values.clear();
+                self.status = match template::forget_values() {
+                    Ok(()) => "Forgot the remembered placeholder values".to_string(),
+                    Err(e) => e,
+                };
+            }
+            None => {}
+        }
+    }
+
+    /// Uses `settings` from now on and saves them. Startup defaults only take
+    /// effect on the next launch.
+    fn apply_settings(&mut self, settings: config::Settings, ctx: &egui::Context) {
+        let (keymap, mut errors) = keymap::Keymap::new(&settings.shortcuts);
+        self.keymap = keymap;
+        let (old, new) = (&self.settings.appearance, &settings.appearance);
+        if (
+            &old.proportional_font,
+            &old.monospace_font,
+            old.cjk_fallback,
+        ) != (
+            &new.proportional_font,
+            &new.monospace_font,
+            new.cjk_fallback,
+        ) {
+            let (fonts, font_errors) = appearance::fonts(new);
+            ctx.set_fonts(fonts);
+            errors.extend(font_errors);
+        }
+        appearance::apply_theme(ctx, new.theme);
+        ctx.set_zoom_factor(appearance::clamp_zoom(new.zoom));
+        if settings.reset != self.settings.reset {
+            self.reset_policy = settings.reset.clone();
+        }
+        self.export_dialog.settings = settings.export.clone();
+        self.history.truncate(settings.history.max_turns);
+        self.settings = settings;
+
+        self.status = match config::save_settings(&self.settings) {
+            Ok(()) if errors.is_empty() => "Settings saved".to_string(),
+            Ok(()) => errors.join("; "),
+            Err(e) => e,
+        };
+    }
+
+    /// Applies the theme, fonts and zoom from the settings at startup.
+    fn apply_appearance(&mut self, ctx: &egui::Context) {
+        let errors = appearance::apply(ctx, &self.settings.appearance);
+        if !errors.is_empty() {
+            self.status = errors.join("; ");
+        }
+    }
+
+    fn set_zoom(&mut self, ctx: &egui::Context, zoom: f32) {
+        let zoom = appearance::clamp_zoom(zoom);
+        ctx.set_zoom_factor(zoom);
+        self.settings.appearance.zoom = zoom;
+        self.status = match config::save_settings(&self.settings) {
+            Ok(()) => format!("Zoom {:.0}%", zoom * 100.0),
+            Err(e) => e,
+        };
+    }
+
+    fn switch_theme(&mut self, ctx: &egui::Context) {
+        let theme = match self.settings.appearance.theme {
+            config::Theme::System => config::Theme::Dark,
+            config::Theme::Dark => config::Theme::Light,
+            config::Theme::Light => config::Theme::System,
+        };
+        appearance::apply_theme(ctx, theme);
+        self.settings.appearance.theme = theme;
+        self.status = match config::save_settings(&self.settings) {
+            Ok(()) => format!("Theme: {}", theme.label()),
+            Err(e) => e,
+        };
+    }
+
+    /// Everything the command palette offers, with the text shown for it.
+    fn palette_entries(&self) -> Vec<(fuzzy::PickerItem, PaletteEntry)> {
+        let item = |label: String, detail: String| fuzzy::PickerItem { label, detail };
+        let description = |meta: &HashMap<String, FrontMatter>, name: &str| {
+            meta.get(name)
+                .and_then(|meta| meta.description.clone())
+                .unwrap_or_default()
+        };
+
+        let mut entries: Vec<(fuzzy::PickerItem, PaletteEntry)> = Command::ALL
+            .iter()
+            .filter(|command| !matches!(command, Command::Palette | Command::Preset(_)))
+            .map(|&command| {
+                let detail = self.shortcut_text(command).unwrap_or_default();
+                (
+                    item(command.label(), detail),
+                    PaletteEntry::Command(command),
+                )
+            })
+            .collect();
+        for model in &self.settings.models {
+            entries.push((
+                item(format!("Model: {}", model.label), model.name.clone()),
+                PaletteEntry::Model(model.name.clone()),
+            ));
+        }
+        for task in &self.tasks {
+            entries.push((
+                item(
+                    format!("Task: {}", task),
+                    description(&self.task_meta, task),
+                ),
+                PaletteEntry::Task(task.clone()),
+            ));
+        }
+        for role in &self.roles {
+            entries.push((
+                item(
+                    format!("Role: {}", role),
+                    description(&self.role_meta, role),
+                ),
+                PaletteEntry::Role(role.clone()),
+            ));
+        }
+        for (index, preset) in self.presets.iter().enumerate() {
+            let detail = u8::try_from(index + 1)
+                .ok()
+                .and_then(|n| self.shortcut_text(Command::Preset(n)))
+                .unwrap_or_default();
+            entries.push((
+                item(format!("Preset: {}", preset.name), detail),
+                PaletteEntry::Preset(index),
+            ));
+        }
+        for (index, action) in self.settings.actions.iter().enumerate() {
+            entries.push((
+                item(
+                    format!("Post-process: {}", action.name),
+                    action.command.clone(),
+                ),
+                PaletteEntry::Action(index),
+            ));
+        }
+        entries
+    }
+
+    fn command_palette(&mut self, ctx: &egui::Context) {
+        if !self.palette.is_open() {
+            return;
+        }
+        let (items, entries): (Vec<_>, Vec<_>) = self.palette_entries().into_iter().unzip();
+        let Some(index) = self.palette.show(ctx, "Command palette", &items) else {
+            return;
+        };
+
+        match &entries[index] {
+            PaletteEntry::Command(command) => self.execute_command(*command, ctx),
+            PaletteEntry::Model(model) => self.model = model.clone(),
+            PaletteEntry::Task(task) => {
+                self.task = task.clone();
+                self.apply_task_metadata();
+            }
+            PaletteEntry::Role(role) => self.role = role.clone(),
+            PaletteEntry::Preset(index) => self.apply_preset(*index),
+            PaletteEntry::Action(index) => self.run_action(*index, ctx),
+        }
+    }
+
     fn send_prompt(&mut self) {
-        self.execute_gia(false);
+        self.prepare_send(SendMode::Send);
     }
 
     fn send_prompt_with_audio(&mut self) {
-        self.execute_gia(true);
+        self.prepare_send(SendMode::Record);
+    }
+
+    /// Asks for template variables first if the prompt or task has any.
+    fn prepare_send(&mut self, mode: SendMode) {
+        if self.template_form.is_some() {
+            return;
+        }
+        if mode != SendMode::Compare && *self.is_executing.lock().unwrap() {
+            self.status = "gia is already executing".to_string();
+            return;
+        }
+        let fields = self.template_fields();
+
+        if fields.is_empty() {
+            if let Err(e) = self.dispatch(mode, &[]) {
+                self.status = e;
+            }
+        } else {
+            self.template_form = Some(TemplateForm {
+                fields,
+                mode,
+                focused: false,
+            });
+        }
+    }
+
+    /// The placeholders of the prompt and the selected tasks, prefilled.
+    fn template_fields(&self) -> Vec<template::Field> {
+        let mut sources = vec![(template::prompt_key(&self.prompt), self.prompt.as_str())];
+        for task in self.selected_tasks() {
+            if let Some(content) = self.task_contents.get(&task) {
+                sources.push((format!("task:{}", task), content.as_str()));
+            }
+        }
+        template::fields(&sources, &self.template_values)
+    }
+
+    fn dispatch(&mut self, mode: SendMode, fields: &[template::Field]) -> Result<(), String> {
+        match mode {
+            SendMode::Send => self.execute_gia(false, fields),
+            SendMode::Record => self.execute_gia(true, fields),
+            SendMode::Compare => self.execute_compare(fields),
+        }
+    }
+
+    fn selected_tasks(&self) -> Vec<String> {
+        std::iter::once(&self.task)
+            .chain(&self.extra_tasks)
+            .filter(|task| !task.is_empty())
+            .cloned()
+            .collect()
+    }
+
+    fn selected_roles(&self) -> Vec<String> {
+        std::iter::once(&self.role)
+            .chain(&self.extra_roles)
+            .filter(|role| !role.is_empty())
+            .cloned()
+            .collect()
+    }
+
+    /// Name to pass to gia for the selected tasks or roles, and the content to
+    /// pass instead if giagui has to render it. gia reads these files itself and
+    /// takes only one of each, so several selections, or a file with template
+    /// syntax or front matter, are rendered first. Front matter is giagui's own
+    /// metadata and is never passed on to gia.
+    fn resolve_selection(
+        &mut self,
+        subdir: &str,
+        fields: &[template::Field],
+        builtins: &template::Builtins,
+    ) -> Result<(String, Option<String>), String> {
+        let (names, contents) = if subdir == library::TASKS {
+            (self.selected_tasks(), &self.task_contents)
+        } else {
+            (self.selected_roles(), &self.role_contents)
+        };
+
+        // (whole file, body without front matter)
+        let files: Vec<(&str, &str)> = names
+            .iter()
+            .filter_map(|name| contents.get(name))
+            .map(|content| (content.as_str(), library::parse_front_matter(content).1))
+            .collect();
+        let needs_rendering = names.len() > 1
+            || files.iter().any(|(content, body)| {
+                content.len() != body.len() || template::has_template_syntax(body)
+            });
+        if !needs_rendering {
+            return Ok((names.into_iter().next().unwrap_or_default(), None));
+        }
+
+        let combined = files
+            .iter()
+            .map(|(_, body)| template::render(body, fields, builtins))
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n\n");
+        Ok((names.join(" + "), Some(combined)))
+    }
+
+    fn template_form_window(&mut self, ctx: &egui::Context) {
+        let Some(form) = &mut self.template_form else {
+            return;
+        };
+
+        let mut submit = false;
+        let mut cancel = false;
+        egui::Window::new("Fill in template")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("template_fields")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (index, field) in form.fields.iter_mut().enumerate() {
+                            ui.label(&field.name);
+                            if field.choices.is_empty() {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut field.value)
+                                        .desired_width(280.0),
+                                );
+                                if index == 0 && !form.focused {
+                                    response.request_focus();
+                                    form.focused = true;
+                                }
+                            } else {
+                                egui::ComboBox::from_id_salt(("template_choice", index))
+                                    .width(280.0)
+                                    .selected_text(&field.value)
+                                    .show_ui(ui, |ui| {
+                                        for choice in &field.choices {
+                                            ui.selectable_value(
+                                                &mut field.value,
+                                                choice.clone(),
+                                                choice,
+                                            );
+                                        }
+                                    });
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    submit = ui.button("Send").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.is_none());
+                    cancel = ui.button("Cancel").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+
+        if submit && let Some(form) = self.template_form.take() {
+            if self.settings.privacy.remember_template_values
+                && let Err(e) = template::remember(&mut self.template_values, &form.fields)
+            {
+                self.status = e;
+            }
+            if let Err(e) = self.dispatch(form.mode, &form.fields) {
+                self.status = e;
+            }
+        } else if cancel {
+            self.template_form = None;
+        }
     }
 
-    fn execute_gia(&mut self, with_audio: bool) {
-        let mut args = vec![];
+    /// Builds the gia invocation for the current form.
+    fn build_request(
+        &mut self,
+        fields: &[template::Field],
+        with_audio: bool,
+        resume: bool,
+        model: &str,
+    ) -> Result<GiaRequest, String> {
+        let builtins = template::Builtins {
+            selection: self.response_selection.clone(),
+            base_dir: self
+                .working_dir
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default(),
+        };
+        let prompt = template::render(&self.prompt, fields, &builtins)?;
+
+        let task = self.resolve_selection(library::TASKS, fields, &builtins)?;
+        let role = self.resolve_selection(library::ROLES, fields, &builtins)?;
+
+        let mut args = self.settings.gia.extra_args.clone();
+        let mut generated = Vec::new();
 
         if with_audio {
             args.push("--record-audio".to_string());
@@ -475,24 +1481,36 @@ impl GiaApp {
         if self.browser_output {
             args.push("--browser-output".to_string());
         }
-        if self.resume {
-            args.push("-R".to_string());
+        if resume {
+            // A plain `-R` resumes the newest conversation on disk, which may
+            // have been started elsewhere (e.g. by a comparison) since the list
+            // was loaded, so a pinned conversation is always named when gia
+            // supports it
+            match &self.active_conversation {
+                Some(id) if self.conversations_unavailable.is_none() => {
+                    args.push(format!("--resume={}", id))
+                }
+                _ => args.push("-R".to_string()),
+            }
         }
 
         // Add model option
         args.push("--model".to_string());
-        args.push(self.model.clone());
+        args.push(model.to_string());
 
-        // Add task option if selected
-        if !self.task.is_empty() {
-            args.push("-t".to_string());
-            args.push(self.task.clone());
-        }
-
-        // Add role option if selected
-        if !self.role.is_empty() {
-            args.push("--role".to_string());
-            args.push(self.role.clone());
+        // Add task and role options if selected
+        for (flag, (name, content)) in [("-t", task), ("--role", role)] {
+            if name.is_empty() {
+                continue;
+            }
+            args.push(flag.to_string());
+            if let Some(content) = content {
+                generated.push(gia::Generated {
+                    arg: args.len(),
+                    content,
+                });
+            }
+            args.push(name);
         }
 
         // Add TTS option if enabled
@@ -508,38 +1526,334 @@ impl GiaApp {
             }
         }
 
-        if !self.prompt.is_empty() {
-            args.push(self.prompt.clone());
+        if !prompt.is_empty() {
+            args.push(prompt.clone());
         }
 
-        // Clear task and role selections, uncheck clipboard, and enable resume after sending
-        self.task.clear();
-        self.role.clear();
-        self.use_clipboard = false;
-        self.resume = true;
+        let working_dir = self.working_dir.clone().or_else(|| {
+            let configured = self.settings.gia.working_dir.trim();
+            (!configured.is_empty()).then(|| PathBuf::from(configured))
+        });
+        Ok(GiaRequest {
+            program: self.settings.gia.program().to_string(),
+            args,
+            working_dir,
+            prompt,
+            model: model.to_string(),
+            task: self.selected_tasks().join(" + "),
+            role: self.selected_roles().join(" + "),
+            generated,
+        })
+    }
 
-        // Start animation
-        *self.is_executing.lock().unwrap() = true;
-        self.animation_time = 0.0;
+    /// Runs the form against every model selected in the compare window, as
+    /// independent requests so the current conversation is not touched.
+    fn execute_compare(&mut self, fields: &[template::Field]) -> Result<(), String> {
+        // Each run starts a conversation of its own in gia, so a plain `-R`
+        // would afterwards continue one of them. Pin the one the user is in.
+        if self.active_conversation.is_none() {
+            self.conversations = conversations::list();
+            self.active_conversation = self.conversations.first().map(|c| c.id.clone());
+            if self.active_conversation.is_none() {
+                self.resume = false;
+            }
+        }
+        let (browser_output, tts_enabled) = (self.browser_output, self.tts_enabled);
+        self.browser_output = false;
+        self.tts_enabled = false;
 
-        let is_executing = Arc::clone(&self.is_executing);
-        let pending_response = Arc::clone(&self.pending_response);
+        let models = self.compare.models.clone();
+        let requests = models
+            .iter()
+            .map(|model| self.build_request(fields, false, false, model))
+            .collect::<Result<Vec<_>, _>>();
+
+        self.browser_output = browser_output;
+        self.tts_enabled = tts_enabled;
+        self.compare.start(requests?);
+        Ok(())
+    }
+
+    fn execute_gia(&mut self, with_audio: bool, fields: &[template::Field]) -> Result<(), String> {
+        if *self.is_executing.lock().unwrap() {
+            return Err("gia is already executing".to_string());
+        }
+        let resume = self.resume;
+        let model = self.model.clone();
+        let request = self.build_request(fields, with_audio, resume, &model)?;
+
+        // Keep the conversation as it is now, in case this turn is regenerated
+        let resumed = if resume {
+            self.active_conversation
+                .clone()
+                .or_else(|| self.conversations.first().map(|c| c.id.clone()))
+                .and_then(|id| conversations::snapshot(&id))
+        } else {
+            None
+        };
+
+        self.reset_after_send();
+        self.spawn_request(request, Origin::Send { resumed });
+        Ok(())
+    }
+
+    /// Runs `request` in the background; the result is picked up in `update`.
+    fn spawn_request(&mut self, request: GiaRequest, origin: Origin) {
+        // Start animation
+        *self.is_executing.lock().unwrap() = true;
+        self.animation_time = 0.0;
+
+        let is_executing = Arc::clone(&self.is_executing);
+        let pending_responses = Arc::clone(&self.pending_responses);
 
         thread::spawn(move || {
-            let result = match Command::new("gia").args(args).output() {
-                Ok(output) => {
-                    let mut response = String::from_utf8_lossy(&output.stdout).to_string();
-                    if !output.stderr.is_empty() {
-                        response.push_str("\n\nErrors:\n");
-                        response.push_str(&String::from_utf8_lossy(&output.stderr));
+            let result = gia::run(request);
+
+            pending_responses.lock().unwrap().push((origin, result));
+            *is_executing.lock().unwrap() = false;
+        });
+    }
+
+    /// Re-runs the exact invocation of the shown response, optionally with
+    /// another model, and keeps the result as a further variant. A turn that
+    /// continued a conversation is re-run against a copy of the conversation
+    /// from before the turn.
+    fn regenerate(&mut self) {
+        if *self.is_executing.lock().unwrap() {
+            return;
+        }
+        let Some(turn) = self.history.current() else {
+            self.status = "Nothing to regenerate yet".to_string();
+            return;
+        };
+
+        let turn_id = turn.id;
+        let mut request = turn.current().request.clone();
+        if !self.regenerate_model.is_empty() && self.regenerate_model != request.model {
+            if let Some(index) = request.args.iter().position(|arg| arg == "--model")
+                && let Some(model) = request.args.get_mut(index + 1)
+            {
+                *model = self.regenerate_model.clone();
+            }
+            request.model = self.regenerate_model.clone();
+        }
+
+        let mut branch = None;
+        if resumes(&request) {
+            match &self.turn_snapshot {
+                Some((turn, snapshot)) if *turn == turn_id => match conversations::branch(snapshot)
+                {
+                    Ok(id) => {
+                        for arg in &mut request.args {
+                            if arg == "-R" || arg.starts_with("--resume") {
+                                *arg = format!("--resume={}", id);
+                            }
+                        }
+                        branch = Some(id);
                     }
-                    response
+                    Err(e) => {
+                        self.status = e;
+                        return;
+                    }
+                },
+                _ => {
+                    self.status = "The conversation was not stored before this turn, so the \
+                                   new variant also sees the first answer"
+                        .to_string();
                 }
-                Err(e) => format!("Error executing gia: {}", e),
-            };
+            }
+        }
 
-            *pending_response.lock().unwrap() = Some(result);
-            *is_executing.lock().unwrap() = false;
+        self.spawn_request(
+            request,
+            Origin::Regenerate {
+                turn: turn_id,
+                branch,
+            },
+        );
+    }
+
+    fn show_variant(&mut self) {
+        if let Some(turn) = self.history.current() {
+            self.response = turn.current().response_text();
+        }
+    }
+
+    fn step_variant(&mut self, offset: isize) {
+        if let Some(turn) = self.history.current_mut()
+            && turn.step(offset)
+        {
+            self.show_variant();
+        }
+    }
+
+    fn variants_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(turn) = self.history.current() else {
+            return;
+        };
+        let count = turn.variants.len();
+        let shown = turn.shown;
+        let preferred = turn.preferred == Some(shown);
+        let current = turn.current();
+        let detail = format!(
+            "{} · {:.1} s",
+            current.request.model,
+            current.duration_ms as f64 / 1000.0
+        );
+        let response_count = self.history.entries().len();
+        let is_executing = *self.is_executing.lock().unwrap();
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_executing, egui::Button::new("🔄 Regenerate"))
+                .on_hover_text(
+                    self.with_shortcut("Run the same request again", Command::Regenerate),
+                )
+                .clicked()
+            {
+                self.regenerate();
+            }
+            egui::ComboBox::from_id_salt("regenerate_model")
+                .selected_text(if self.regenerate_model.is_empty() {
+                    "Same model"
+                } else {
+                    &self.regenerate_model
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.regenerate_model, String::new(), "Same model");
+                    for model in &self.settings.models {
+                        ui.selectable_value(
+                            &mut self.regenerate_model,
+                            model.name.clone(),
+                            &model.label,
+                        );
+                    }
+                });
+
+            if count > 1 {
+                ui.separator();
+                if ui
+                    .add_enabled(shown > 0, egui::Button::new("◀"))
+                    .on_hover_text(self.with_shortcut("Previous variant", Command::PreviousVariant))
+                    .clicked()
+                {
+                    self.step_variant(-1);
+                }
+                ui.label(format!("{}/{}", shown + 1, count));
+                if ui
+                    .add_enabled(shown + 1 < count, egui::Button::new("▶"))
+                    .on_hover_text(self.with_shortcut("Next variant", Command::NextVariant))
+                    .clicked()
+                {
+                    self.step_variant(1);
+                }
+                let star = if preferred {
+                    "★ Preferred"
+                } else {
+                    "☆ Prefer"
+                };
+                if ui.selectable_label(preferred, star).clicked()
+                    && let Some(turn) = self.history.current_mut()
+                {
+                    turn.toggle_preferred();
+                }
+            }
+            if ui
+                .add_enabled(response_count > 1, egui::Button::new("Diff..."))
+                .on_hover_text(self.with_shortcut("Compare stored responses", Command::Diff))
+                .clicked()
+            {
+                self.open_diff();
+            }
+            ui.weak(detail);
+        });
+    }
+
+    fn open_diff(&mut self) {
+        let count = self.history.entries().len();
+        if count < 2 {
+            self.status = "A diff needs at least two responses".to_string();
+            return;
+        }
+        self.diff_view.open(count);
+    }
+
+    fn diff_window(&mut self, ctx: &egui::Context) {
+        if !self.diff_view.is_open() {
+            return;
+        }
+        let entries: Vec<(String, String)> = self
+            .history
+            .entries()
+            .into_iter()
+            .map(|(label, result)| (label, result.response_text()))
+            .collect();
+        self.diff_view.show(ctx, &entries);
+    }
+
+    /// Resets the fields selected in the reset policy after a prompt was sent.
+    fn reset_after_send(&mut self) {
+        let policy = self.reset_policy.clone();
+
+        if policy.clear_prompt {
+            self.prompt.clear();
+        }
+        if policy.clear_task {
+            self.task.clear();
+            self.extra_tasks.clear();
+        }
+        if policy.clear_role {
+            self.role.clear();
+            self.extra_roles.clear();
+        }
+        if policy.clear_clipboard {
+            self.use_clipboard = false;
+        }
+        if policy.enable_resume {
+            self.resume = true;
+        }
+        if policy.clear_options {
+            self.options.clear();
+        } else if policy.clear_attachments {
+            self.options = self
+                .options
+                .lines()
+                .filter(|line| {
+                    let line = line.trim_start();
+                    !line.starts_with("-f") && !line.starts_with("-i")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+    }
+
+    fn reset_policy_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.weak(self.reset_policy.summary());
+            ui.menu_button("⚙", |ui| {
+                let policy = &mut self.reset_policy;
+                ui.label("After sending:");
+                ui.checkbox(&mut policy.clear_prompt, "Clear prompt");
+                ui.checkbox(&mut policy.clear_task, "Clear task");
+                ui.checkbox(&mut policy.clear_role, "Clear role");
+                ui.checkbox(&mut policy.clear_clipboard, "Uncheck clipboard");
+                ui.checkbox(&mut policy.enable_resume, "Enable resume");
+                ui.checkbox(&mut policy.clear_attachments, "Remove attachments");
+                ui.checkbox(&mut policy.clear_options, "Clear options");
+                ui.separator();
+                if ui.button("Save as default").clicked() {
+                    self.settings.reset = self.reset_policy.clone();
+                    self.status = match config::save_settings(&self.settings) {
+                        Ok(()) => "Saved post-send reset as default".to_string(),
+                        Err(e) => e,
+                    };
+                    ui.close_menu();
+                }
+                if ui.button("Restore default").clicked() {
+                    self.reset_policy = self.settings.reset.clone();
+                    ui.close_menu();
+                }
+            });
         });
     }
 
@@ -550,6 +1864,184 @@ impl GiaApp {
         self.use_clipboard = false;
         self.browser_output = false;
         self.resume = false;
+        self.active_conversation = None;
+    }
+
+    fn conversation_label(&self, id: &str) -> String {
+        if let Some(name) = self.conversation_names.get(id) {
+            return name.clone();
+        }
+        match self.conversations.iter().find(|c| c.id == id) {
+            Some(conversation)
+                if self.settings.privacy.show_conversation_previews
+                    && !conversation.preview.is_empty() =>
+            {
+                conversation.preview.clone()
+            }
+            _ => id.to_string(),
+        }
+    }
+
+    /// The conversation the next send continues, shown next to the buttons.
+    fn active_conversation_label(&self) -> String {
+        if !self.resume {
+            return "New conversation".to_string();
+        }
+        match self
+            .active_conversation
+            .as_ref()
+            .or(self.conversations.first().map(|c| &c.id))
+        {
+            Some(id) => self.conversation_label(id),
+            None => "Last conversation".to_string(),
+        }
+    }
+
+    fn new_conversation(&mut self) {
+        self.resume = false;
+        self.active_conversation = None;
+        self.status = "The next prompt starts a new conversation".to_string();
+    }
+
+    fn resume_conversation(&mut self, id: &str) {
+        self.resume = true;
+        self.active_conversation = Some(id.to_string());
+        self.status = format!("Resuming '{}'", self.conversation_label(id));
+    }
+
+    fn rename_conversation(&mut self, id: String, name: String) {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            self.conversation_names.remove(&id);
+        } else {
+            self.conversation_names.insert(id, name);
+        }
+        if let Err(e) = conversations::save_names(&self.conversation_names) {
+            self.status = e;
+        }
+    }
+
+    fn conversation_row_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("💬");
+            ui.strong(self.active_conversation_label());
+            if ui.button("New").clicked() {
+                self.new_conversation();
+            }
+            if ui.button("Conversations...").clicked() {
+                self.execute_command(Command::Conversations, ui.ctx());
+            }
+        });
+    }
+
+    fn refresh_conversations(&mut self) {
+        self.conversations_unavailable = conversations::check(self.settings.gia.program()).err();
+        self.conversations = conversations::list();
+    }
+
+    fn conversations_window(&mut self, ctx: &egui::Context) {
+        if !self.show_conversations_window {
+            return;
+        }
+
+        let mut open = true;
+        let mut resume = None;
+        let mut rename = None;
+        let mut start_rename = None;
+        let labels: Vec<String> = self
+            .conversations
+            .iter()
+            .map(|conversation| self.conversation_label(&conversation.id))
+            .collect();
+        let active = self
+            .resume
+            .then(|| {
+                self.active_conversation
+                    .clone()
+                    .or(self.conversations.first().map(|c| c.id.clone()))
+            })
+            .flatten();
+
+        egui::Window::new("Conversations")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("New conversation").clicked() {
+                        self.new_conversation();
+                    }
+                    if ui.button("Refresh").clicked() {
+                        self.refresh_conversations();
+                    }
+                });
+                ui.separator();
+
+                if let Some(reason) = &self.conversations_unavailable {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "Conversation browser unavailable",
+                    );
+                    ui.label(reason);
+                    return;
+                }
+                if self.conversations.is_empty() {
+                    ui.weak("No stored conversations in ~/.gia/conversations");
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let shown = match self.settings.history.max_conversations {
+                        0 => self.conversations.len(),
+                        max => max,
+                    };
+                    for (conversation, label) in self.conversations.iter().take(shown).zip(labels) {
+                        ui.horizontal(|ui| {
+                            let renaming = self
+                                .conversation_rename
+                                .as_mut()
+                                .filter(|(id, _)| *id == conversation.id);
+                            if let Some((_, name)) = renaming {
+                                let response = ui.text_edit_singleline(name);
+                                if ui.button("OK").clicked()
+                                    || (response.lost_focus()
+                                        && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                                {
+                                    rename = Some(conversation.id.clone());
+                                }
+                            } else {
+                                let is_active = active.as_ref() == Some(&conversation.id);
+                                if ui
+                                    .selectable_label(is_active, label)
+                                    .on_hover_text(&conversation.id)
+                                    .clicked()
+                                {
+                                    resume = Some(conversation.id.clone());
+                                }
+                                ui.weak(conversations::age(conversation.modified));
+                                if ui.small_button("Rename").clicked() {
+                                    start_rename = Some(conversation.id.clone());
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+
+        if let Some(id) = resume {
+            self.resume_conversation(&id);
+        }
+        if let Some(id) = rename
+            && let Some((_, name)) = self.conversation_rename.take()
+        {
+            self.rename_conversation(id, name);
+        }
+        if let Some(id) = start_rename {
+            let name = self
+                .conversation_names
+                .get(&id)
+                .cloned()
+                .unwrap_or_default();
+            self.conversation_rename = Some((id, name));
+        }
+        self.show_conversations_window = open;
     }
 
     fn copy_response(&mut self) {
@@ -558,6 +2050,237 @@ impl GiaApp {
         }
     }
 
+    fn open_in_editor(&mut self, target: editor::Target, ctx: &egui::Context) {
+        if self.is_editing(target) {
+            return;
+        }
+        let Some(command) = editor::command(&self.settings.editor) else {
+            self.status = "Set $VISUAL or $EDITOR, or an editor in settings.toml".to_string();
+            return;
+        };
+        let text = match target {
+            editor::Target::Prompt => &self.prompt,
+            editor::Target::Response => &self.response,
+        };
+        match editor::open(
+            target,
+            text,
+            &command,
+            Arc::clone(&self.editor_events),
+            ctx.clone(),
+        ) {
+            Ok(session) => {
+                self.editing.push(session);
+                self.status = format!("Editing the {} in {}", target.label(), command);
+            }
+            Err(e) => self.status = e,
+        }
+    }
+
+    fn handle_editor_events(&mut self) {
+        let events = std::mem::take(&mut *self.editor_events.lock().unwrap());
+        for event in events {
+            match event {
+                editor::Event::Changed(target, text) => {
+                    // Editors usually add a final newline the field did not have
+                    let text = text.strip_suffix('\n').unwrap_or(&text).to_string();
+                    match target {
+                        editor::Target::Prompt => self.prompt = text,
+                        editor::Target::Response => self.response = text,
+                    }
+                    self.status = format!("Imported the {} from the editor", target.label());
+                }
+                editor::Event::Closed(target) => {
+                    self.editing.retain(|session| session.target != target)
+                }
+                editor::Event::Failed(target, e) => {
+                    self.status = format!("Failed to import the {}: {}", target.label(), e);
+                }
+            }
+        }
+    }
+
+    fn is_editing(&self, target: editor::Target) -> bool {
+        self.editing.iter().any(|session| session.target == target)
+    }
+
+    fn editor_button(&mut self, ui: &mut egui::Ui, target: editor::Target, command: Command) {
+        if let Some(session) = self.editing.iter().find(|session| session.target == target) {
+            ui.weak("editing externally...");
+            if ui
+                .small_button("Done")
+                .on_hover_text("Stop importing saves from the editor")
+                .clicked()
+            {
+                session.stop();
+            }
+        } else if ui
+            .small_button("✏")
+            .on_hover_text(self.with_shortcut("Open in external editor", command))
+            .clicked()
+        {
+            self.open_in_editor(target, ui.ctx());
+        }
+    }
+
+    /// Runs the post-processing action `index` on the response in the background.
+    fn run_action(&mut self, index: usize, ctx: &egui::Context) {
+        let Some(action) = self.settings.actions.get(index).cloned() else {
+            return;
+        };
+        let text = self.response.clone();
+        let working_dir = self.working_dir.clone();
+        let results = Arc::clone(&self.action_results);
+        let ctx = ctx.clone();
+
+        self.status = format!("Running '{}'...", action.name);
+        thread::spawn(move || {
+            let output = postprocess::run(&action, &text, working_dir.as_deref());
+            results.lock().unwrap().push(postprocess::Finished {
+                action,
+                input: text,
+                output,
+            });
+            ctx.request_repaint();
+        });
+    }
+
+    fn handle_action_result(&mut self) {
+        let finished = std::mem::take(&mut *self.action_results.lock().unwrap());
+        for run in finished {
+            self.apply_action_result(run);
+        }
+    }
+
+    fn apply_action_result(&mut self, run: postprocess::Finished) {
+        let postprocess::Finished {
+            action,
+            input,
+            output,
+        } = run;
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                self.status = e;
+                return;
+            }
+        };
+
+        self.status = match action.output {
+            // Only the response the action ran on is replaced
+            postprocess::Output::Replace if self.response != input => format!(
+                "The response changed while '{}' ran; its output was not applied",
+                action.name
+            ),
+            postprocess::Output::Replace => {
+                self.response = output;
+                format!("Response replaced by the output of '{}'", action.name)
+            }
+            postprocess::Output::Clipboard => {
+                match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(output)) {
+                    Ok(()) => format!("Output of '{}' copied to the clipboard", action.name),
+                    Err(e) => format!("Failed to copy the output of '{}': {}", action.name, e),
+                }
+            }
+            postprocess::Output::File => {
+                let sample = self.shown_result();
+                let file_name = export::file_name(
+                    &format!("{{date}}_{{time}}_{}", action.name),
+                    &sample,
+                    &action.extension,
+                );
+                let directory = PathBuf::from(&self.settings.export.directory);
+                match export::write(&directory, &file_name, &output) {
+                    Ok(path) => format!("Output of '{}' saved to {}", action.name, path.display()),
+                    Err(e) => e,
+                }
+            }
+        };
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) {
+        if self.settings.actions.is_empty() {
+            return;
+        }
+        let mut run = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Post-process:");
+            for (index, action) in self.settings.actions.iter().enumerate() {
+                if ui
+                    .button(&action.name)
+                    .on_hover_text(&action.command)
+                    .clicked()
+                {
+                    run = Some(index);
+                }
+            }
+        });
+        if let Some(index) = run {
+            self.run_action(index, ui.ctx());
+        }
+    }
+
+    /// The shown response as a result, including edits made in the response box.
+    fn shown_result(&self) -> GiaResult {
+        let mut result = self
+            .history
+            .current()
+            .map(|turn| turn.current().clone())
+            .unwrap_or_default();
+        result.stdout = self.response.clone();
+        result.stderr.clear();
+        result.error = None;
+        result
+    }
+
+    /// One response per turn of the session: the preferred variant, or the shown one.
+    fn session_results(&self) -> Vec<GiaResult> {
+        let mut results: Vec<GiaResult> = self
+            .history
+            .turns
+            .iter()
+            .map(|turn| turn.variants[turn.preferred.unwrap_or(turn.shown)].clone())
+            .collect();
+        if results.is_empty() {
+            results.push(self.shown_result());
+        }
+        results
+    }
+
+    fn export_window(&mut self, ctx: &egui::Context) {
+        let sample = self.shown_result();
+        match self.export_dialog.show(ctx, &sample) {
+            Some(export::DialogAction::Export) => {
+                let dialog = &self.export_dialog;
+                let results = if dialog.session {
+                    self.session_results()
+                } else {
+                    vec![sample.clone()]
+                };
+                let file_name = export::file_name(
+                    &dialog.settings.file_name,
+                    &sample,
+                    dialog.format.extension(),
+                );
+                let directory = PathBuf::from(&dialog.settings.directory);
+                self.status = match export::render(&results, dialog.format)
+                    .and_then(|content| export::write(&directory, &file_name, &content))
+                {
+                    Ok(path) => format!("Exported to {}", path.display()),
+                    Err(e) => e,
+                };
+            }
+            Some(export::DialogAction::SaveDefaults) => {
+                self.settings.export = self.export_dialog.settings.clone();
+                self.status = match config::save_settings(&self.settings) {
+                    Ok(()) => "Saved export defaults".to_string(),
+                    Err(e) => e,
+                };
+            }
+            None => {}
+        }
+    }
+
     fn show_conversation(&mut self) {
         let mut args = vec!["--show-conversation".to_string()];
 
@@ -565,11 +2288,598 @@ impl GiaApp {
             args.push(format!("--tts={}", self.tts_language));
         }
 
-        let _ = Command::new("gia").args(args).spawn();
+        let _ = process::Command::new(self.settings.gia.program())
+            .args(args)
+            .spawn();
+    }
+
+    fn start_api(&mut self, port: u16, ctx: &egui::Context) {
+        let started = api::load_token().and_then(|token| {
+            api::ApiServer::start(port, token, Arc::clone(&self.is_executing), ctx.clone())
+        });
+        match started {
+            Ok(server) => {
+                self.status = format!("Control API listening on http://127.0.0.1:{}", server.port);
+                self.api = Some(server);
+            }
+            Err(e) => self.status = e,
+        }
+    }
+
+    fn handle_api_commands(&mut self) {
+        let Some(commands) = self.api.as_ref().map(|api| api.take_commands()) else {
+            return;
+        };
+
+        for command in commands {
+            match command {
+                api::ApiCommand::SetState(update) => self.apply_state_update(update),
+                api::ApiCommand::Execute {
+                    with_audio,
+                    values,
+                    reply,
+                } => {
+                    let _ = reply.send(self.execute_from_api(with_audio, &values));
+                }
+            }
+        }
+
+        if let Some(api) = &self.api {
+            api.publish_status(api::ApiStatus {
+                is_executing: *self.is_executing.lock().unwrap(),
+                pending_commands: 0,
+                prompt: self.prompt.clone(),
+                options: self.options.clone(),
+                model: self.model.clone(),
+                task: self.task.clone(),
+                role: self.role.clone(),
+                use_clipboard: self.use_clipboard,
+                browser_output: self.browser_output,
+                resume: self.resume,
+                tts_enabled: self.tts_enabled,
+                tts_language: self.tts_language.clone(),
+            });
+        }
+    }
+
+    /// Sends the form for `POST /execute`. Placeholders take their values from
+    /// the request, falling back to remembered values and defaults; the
+    /// template form is never opened since nobody may be at the window.
+    fn execute_from_api(
+        &mut self,
+        with_audio: bool,
+        values: &HashMap<String, String>,
+    ) -> Result<(), String> {
+        if *self.is_executing.lock().unwrap() {
+            return Err("gia is already executing".to_string());
+        }
+        if self.template_form.is_some() {
+            return Err("the template form is open in the window".to_string());
+        }
+
+        let mut fields = self.template_fields();
+        let mut missing = Vec::new();
+        for field in &mut fields {
+            if let Some(value) = values.get(&field.name) {
+                field.value = value.clone();
+            } else if field.value.is_empty() {
+                missing.push(field.name.clone());
+            }
+        }
+        if !missing.is_empty() {
+            return Err(format!(
+                "no value for template placeholders: {}",
+                missing.join(", ")
+            ));
+        }
+
+        let mode = if with_audio {
+            SendMode::Record
+        } else {
+            SendMode::Send
+        };
+        self.dispatch(mode, &fields)
+    }
+
+    fn apply_state_update(&mut self, update: api::StateUpdate) {
+        if let Some(prompt) = update.prompt {
+            self.prompt = prompt;
+        }
+        if let Some(options) = update.options {
+            self.options = options;
+        }
+        if let Some(model) = update.model {
+            self.model = model;
+        }
+        // A task or role set over the API replaces the whole selection
+        if let Some(task) = update.task {
+            self.task = task;
+            self.extra_tasks.clear();
+        }
+        if let Some(role) = update.role {
+            self.role = role;
+            self.extra_roles.clear();
+        }
+        if let Some(use_clipboard) = update.use_clipboard {
+            self.use_clipboard = use_clipboard;
+        }
+        if let Some(browser_output) = update.browser_output {
+            self.browser_output = browser_output;
+        }
+        if let Some(resume) = update.resume {
+            self.resume = resume;
+        }
+        if let Some(tts_enabled) = update.tts_enabled {
+            self.tts_enabled = tts_enabled;
+        }
+        if let Some(tts_language) = update.tts_language {
+            self.tts_language = tts_language;
+        }
+    }
+
+    fn apply_cli_args(&mut self, args: cli::CliArgs) {
+        if let Some(name) = args.preset {
+            match self.presets.iter().position(|preset| preset.name == name) {
+                Some(index) => self.apply_preset(index),
+                None => self.status = format!("Unknown preset '{}'", name),
+            }
+        }
+        if let Some(prompt) = args.prompt {
+            self.prompt = prompt;
+        }
+        if let Some(model) = args.model {
+            self.model = model;
+        }
+        if !args.tasks.is_empty() {
+            self.task.clear();
+            self.extra_tasks.clear();
+            for task in args.tasks {
+                add_selection(&mut self.task, &mut self.extra_tasks, task);
+            }
+        }
+        if !args.roles.is_empty() {
+            self.role.clear();
+            self.extra_roles.clear();
+            for role in args.roles {
+                add_selection(&mut self.role, &mut self.extra_roles, role);
+            }
+        }
+        if let Some(path) = args.options_file {
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                        self.push_option_line(line.trim());
+                    }
+                }
+                Err(e) => self.status = format!("Failed to read {}: {}", path.display(), e),
+            }
+        }
+        for path in args.attach {
+            self.attach_path(&path);
+        }
+        if args.send {
+            self.send_prompt();
+        }
+    }
+
+    fn attach_path(&mut self, path: &Path) {
+        if path.is_dir() {
+            // Add the files of the directory, as configured under Attachments
+            let options = &self.settings.attachments;
+            let mut files_to_add = Vec::new();
+            collect_files_recursive(path, options, &mut files_to_add);
+            if options.max_files > 0 && files_to_add.len() > options.max_files {
+                self.status = format!(
+                    "Attached the first {} of {} files in {}",
+                    options.max_files,
+                    files_to_add.len(),
+                    path.display()
+                );
+                files_to_add.sort();
+                files_to_add.truncate(options.max_files);
+            }
+
+            for file_path in files_to_add {
+                self.attach_file(&file_path);
+            }
+        } else {
+            self.attach_file(path);
+        }
+    }
+
+    fn attach_file(&mut self, path: &Path) {
+        if let Some(path_str) = path.to_str() {
+            let option_line = if self.settings.attachments.is_media(path) {
+                format!("-i{}", path_str)
+            } else {
+                format!("-f{}", path_str)
+            };
+
+            self.push_option_line(&option_line);
+        }
+    }
+
+    fn push_option_line(&mut self, line: &str) {
+        if self.options.lines().any(|existing| existing.trim() == line) {
+            return;
+        }
+        if !self.options.is_empty() && !self.options.ends_with('\n') {
+            self.options.push('\n');
+        }
+        self.options.push_str(line);
+    }
+
+    fn load_workspace(&mut self, dir: &Path) {
+        match Workspace::discover(dir) {
+            // Re-applying the active workspace would undo the user's changes to the form
+            Some(Ok(workspace))
+                if self
+                    .workspace
+                    .as_ref()
+                    .is_some_and(|active| active.root == workspace.root) => {}
+            Some(Ok(workspace)) => self.apply_workspace(workspace),
+            Some(Err(e)) => self.status = e,
+            None => {}
+        }
+    }
+
+    fn apply_workspace(&mut self, workspace: Workspace) {
+        let config = workspace.config.clone();
+
+        if let Some(model) = config.model {
+            self.model = model;
+        }
+        if let Some(task) = config.task {
+            self.task = task;
+        }
+        if let Some(role) = config.role {
+            self.role = role;
+        }
+        if let Some(tts_enabled) = config.tts_enabled {
+            self.tts_enabled = tts_enabled;
+        }
+        if let Some(tts_language) = config.tts_language {
+            self.tts_language = tts_language;
+        }
+        if let Some(use_clipboard) = config.use_clipboard {
+            self.use_clipboard = use_clipboard;
+        }
+        if let Some(browser_output) = config.browser_output {
+            self.browser_output = browser_output;
+        }
+        if let Some(resume) = config.resume {
+            self.resume = resume;
+        }
+        if let Some(options) = config.options {
+            self.options = options;
+        }
+        for path in workspace.attachments() {
+            self.attach_path(&path);
+        }
+
+        self.working_dir = Some(workspace.working_dir());
+        self.status = format!("Workspace: {}", workspace.root.display());
+        self.workspace = Some(workspace);
+        self.title_dirty = true;
+    }
+
+    fn window_title(&self) -> String {
+        match &self.workspace {
+            Some(workspace) => format!("GIA GUI - {}", workspace.name()),
+            None => "GIA GUI".to_string(),
+        }
+    }
+
+    fn current_preset(&self, name: &str) -> Preset {
+        Preset {
+            name: name.to_string(),
+            model: self.model.clone(),
+            task: self.task.clone(),
+            role: self.role.clone(),
+            extra_tasks: self.extra_tasks.clone(),
+            extra_roles: self.extra_roles.clone(),
+            tts_enabled: self.tts_enabled,
+            tts_language: self.tts_language.clone(),
+            use_clipboard: self.use_clipboard,
+            browser_output: self.browser_output,
+            resume: self.resume,
+            options: self.options.clone(),
+            reset: (self.reset_policy != self.settings.reset).then(|| self.reset_policy.clone()),
+        }
+    }
+
+    fn apply_preset(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index).cloned() else {
+            return;
+        };
+
+        if !preset.model.is_empty() {
+            self.model = preset.model;
+        }
+        if !preset.tts_language.is_empty() {
+            self.tts_language = preset.tts_language;
+        }
+        self.task = preset.task;
+        self.role = preset.role;
+        self.extra_tasks = preset.extra_tasks;
+        self.extra_roles = preset.extra_roles;
+        self.tts_enabled = preset.tts_enabled;
+        self.use_clipboard = preset.use_clipboard;
+        self.browser_output = preset.browser_output;
+        self.resume = preset.resume;
+        self.options = preset.options;
+        self.reset_policy = preset.reset.unwrap_or_else(|| self.settings.reset.clone());
+        self.preset_name = preset.name.clone();
+        self.status = format!("Applied preset '{}'", preset.name);
+        self.selected_preset = preset.name;
+    }
+
+    fn save_preset(&mut self) {
+        let name = self.preset_name.trim().to_string();
+        if name.is_empty() {
+            self.status = "Enter a preset name first".to_string();
+            return;
+        }
+
+        let preset = self.current_preset(&name);
+        presets::upsert(&mut self.presets, preset);
+        self.selected_preset = name.clone();
+        self.persist_presets(format!("Saved preset '{}'", name));
+    }
+
+    fn update_preset(&mut self) {
+        let name = self.selected_preset.clone();
+        let preset = self.current_preset(&name);
+        presets::upsert(&mut self.presets, preset);
+        self.persist_presets(format!("Updated preset '{}'", name));
+    }
+
+    fn delete_preset(&mut self) {
+        let name = std::mem::take(&mut self.selected_preset);
+        self.presets.retain(|preset| preset.name != name);
+        self.persist_presets(format!("Deleted preset '{}'", name));
+    }
+
+    fn persist_presets(&mut self, message: String) {
+        self.status = match presets::save_presets(&self.presets) {
+            Ok(()) => message,
+            Err(e) => e,
+        };
+    }
+
+    fn reload_library(&mut self) {
+        self.tasks = library::load_md_files(library::TASKS);
+        self.roles = library::load_md_files(library::ROLES);
+        self.task_contents = library::load_md_contents(library::TASKS, &self.tasks);
+        self.role_contents = library::load_md_contents(library::ROLES, &self.roles);
+        self.task_meta = library::load_front_matter(&self.task_contents);
+        self.role_meta = library::load_front_matter(&self.role_contents);
+        if !self.task_tags().contains(&self.task_tag) {
+            self.task_tag.clear();
+        }
+
+        // Drop selections whose file no longer exists
+        if !self.task.is_empty() && !self.tasks.contains(&self.task) {
+            self.status = format!("Task '{}' was removed", self.task);
+            self.task.clear();
+        }
+        if !self.role.is_empty() && !self.roles.contains(&self.role) {
+            self.status = format!("Role '{}' was removed", self.role);
+            self.role.clear();
+        }
+        let tasks = &self.tasks;
+        self.extra_tasks.retain(|task| tasks.contains(task));
+        let roles = &self.roles;
+        self.extra_roles.retain(|role| roles.contains(role));
+    }
+
+    fn watch_library(&mut self, ctx: &egui::Context) {
+        let dirs = [library::TASKS, library::ROLES]
+            .into_iter()
+            .filter_map(library::md_dir)
+            .collect();
+        self.library_watcher = Some(watcher::LibraryWatcher::start(dirs, ctx.clone()));
+    }
+
+    fn apply_library_change(&mut self, change: manager::LibraryChange) {
+        if let Some((old_name, new_name)) = change.renamed {
+            let (selection, extras) = if change.subdir == library::TASKS {
+                (&mut self.task, &mut self.extra_tasks)
+            } else {
+                (&mut self.role, &mut self.extra_roles)
+            };
+            for name in std::iter::once(selection).chain(extras.iter_mut()) {
+                if *name == old_name {
+                    *name = new_name.clone();
+                }
+            }
+        }
+        // Deleted files drop out of the selections here
+        self.reload_library();
+    }
+
+    fn md_pickers(&mut self, ctx: &egui::Context) {
+        let picker_items = |names: &[String], meta: &HashMap<String, FrontMatter>| {
+            names
+                .iter()
+                .map(|name| fuzzy::PickerItem {
+                    label: name.clone(),
+                    detail: meta
+                        .get(name)
+                        .and_then(|meta| meta.description.clone())
+                        .unwrap_or_default(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let tasks = self.filtered_tasks();
+        let items = picker_items(&tasks, &self.task_meta);
+        if let Some(index) = self.task_picker.show(ctx, "Select Task", &items) {
+            if self.task_picker_adds {
+                add_selection(&mut self.task, &mut self.extra_tasks, tasks[index].clone());
+            } else {
+                self.task = tasks[index].clone();
+                self.apply_task_metadata();
+            }
+        }
+
+        let items = picker_items(&self.roles, &self.role_meta);
+        if let Some(index) = self.role_picker.show(ctx, "Select Role", &items) {
+            if self.role_picker_adds {
+                add_selection(
+                    &mut self.role,
+                    &mut self.extra_roles,
+                    self.roles[index].clone(),
+                );
+            } else {
+                self.role = self.roles[index].clone();
+            }
+        }
+    }
+
+    fn task_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .task_meta
+            .values()
+            .flat_map(|meta| meta.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn filtered_tasks(&self) -> Vec<String> {
+        self.tasks
+            .iter()
+            .filter(|task| {
+                self.task_tag.is_empty()
+                    || self
+                        .task_meta
+                        .get(*task)
+                        .is_some_and(|meta| meta.tags.contains(&self.task_tag))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Applies the suggestions from the selected task's front matter.
+    fn apply_task_metadata(&mut self) {
+        let Some(meta) = self.task_meta.get(&self.task).cloned() else {
+            return;
+        };
+
+        if let Some(model) = meta.model {
+            self.model = model;
+        }
+        if let Some(role) = meta.role {
+            self.role = role;
+        }
+        if let Some(tts) = meta.tts {
+            self.tts_enabled = tts;
+        }
+        if let Some(tts_language) = meta.tts_language {
+            self.tts_language = tts_language;
+        }
+
+        let base_dir = self
+            .working_dir
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        for attachment in meta.attachments {
+            let path = base_dir.join(&attachment);
+            let already_attached = self
+                .options
+                .lines()
+                .any(|line| line.trim().ends_with(path.to_string_lossy().as_ref()));
+            if !already_attached {
+                self.attach_path(&path);
+            }
+        }
+    }
+
+    fn preview_panel(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("preview_panel")
+            .resizable(true)
+            .default_width(280.0)
+            .show_animated(ctx, self.show_preview, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (kind, names, contents) in [
+                        ("Task", self.selected_tasks(), &self.task_contents),
+                        ("Role", self.selected_roles(), &self.role_contents),
+                    ] {
+                        if names.is_empty() {
+                            ui.weak(format!("No {} selected", kind.to_lowercase()));
+                        }
+                        for name in names {
+                            egui::CollapsingHeader::new(format!("{}: {}", kind, name))
+                                .id_salt((kind, &name))
+                                .default_open(true)
+                                .show(ui, |ui| match contents.get(&name) {
+                                    Some(content) => markdown::show(ui, content),
+                                    None => {
+                                        ui.weak("File not found");
+                                    }
+                                });
+                        }
+                    }
+                });
+            });
+    }
+
+    fn presets_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_presets_window;
+        egui::Window::new("Import/Export Presets")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("TOML file:");
+                ui.add(egui::TextEdit::singleline(&mut self.preset_path).desired_width(360.0));
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        let path = PathBuf::from(&self.preset_path);
+                        match presets::import_presets(&path) {
+                            Ok(imported) => {
+                                let count = imported.len();
+                                for preset in imported {
+                                    presets::upsert(&mut self.presets, preset);
+                                }
+                                self.persist_presets(format!("Imported {} preset(s)", count));
+                            }
+                            Err(e) => self.status = e,
+                        }
+                    }
+                    if ui.button("Export").clicked() {
+                        let path = PathBuf::from(&self.preset_path);
+                        self.status = match presets::export_presets(&path, &self.presets) {
+                            Ok(()) => format!(
+                                "Exported {} preset(s) to {}",
+                                self.presets.len(),
+                                path.display()
+                            ),
+                            Err(e) => e,
+                        };
+                    }
+                    if ui.button("Copy as TOML").clicked() {
+                        match presets::to_toml(&self.presets) {
+                            Ok(content) => {
+                                if let Ok(mut clipboard) = Clipboard::new() {
+                                    let _ = clipboard.set_text(content);
+                                }
+                            }
+                            Err(e) => self.status = e,
+                        }
+                    }
+                });
+            });
+        self.show_presets_window = open;
     }
 
     fn show_help(&mut self) {
-        match Command::new("gia").arg("--help").output() {
+        match process::Command::new(self.settings.gia.program())
+            .arg("--help")
+            .output()
+        {
             Ok(output) => {
                 self.response = String::from_utf8_lossy(&output.stdout).to_string();
                 if !output.stderr.is_empty() {
@@ -0,0 +1,362 @@
+//! Window for creating, editing, renaming and deleting task and role files.
+
+use eframe::egui;
+use std::fs;
+use std::time::SystemTime;
+
+use crate::library::{self, ROLES, SaveError, TASKS};
+use crate::markdown;
+
+/// What changed on disk, so the app can refresh its lists and selections.
+pub struct LibraryChange {
+    pub subdir: &'static str,
+    pub renamed: Option<(String, String)>,
+}
+
+pub struct Manager {
+    pub open: bool,
+    subdir: &'static str,
+    selected: Option<String>,
+    editor: String,
+    loaded_at: Option<SystemTime>,
+    dirty: bool,
+    conflict: bool,
+    name_input: String,
+    confirm_delete: bool,
+    message: String,
+}
+
+impl Default for Manager {
+    fn default() -> Self {
+        Self {
+            open: false,
+            subdir: TASKS,
+            selected: None,
+            editor: String::new(),
+            loaded_at: None,
+            dirty: false,
+            conflict: false,
+            name_input: String::new(),
+            confirm_delete: false,
+            message: String::new(),
+        }
+    }
+}
+
+impl Manager {
+    fn select(&mut self, name: Option<String>) {
+        self.editor = name
+            .as_deref()
+            .and_then(|name| library::md_file_path(self.subdir, name))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        self.loaded_at = name
+            .as_deref()
+            .and_then(|name| library::modified(self.subdir, name));
+        self.selected = name;
+        self.dirty = false;
+        self.conflict = false;
+        self.confirm_delete = false;
+    }
+
+    fn check_disk(&mut self) {
+        let Some(name) = &self.selected else {
+            return;
+        };
+        if library::modified(self.subdir, name) == self.loaded_at {
+            return;
+        }
+        let exists = library::md_file_path(self.subdir, name).is_some_and(|path| path.is_file());
+        if self.dirty {
+            self.conflict = true;
+        } else if exists {
+            self.select(self.selected.clone());
+        } else {
+            self.select(None);
+        }
+    }
+
+    fn save(&mut self, force: bool) -> Option<LibraryChange> {
+        let name = self.selected.clone()?;
+        let loaded_at = if force { None } else { self.loaded_at };
+        match library::save(self.subdir, &name, &self.editor, loaded_at) {
+            Ok(modified) => {
+                self.loaded_at = modified;
+                self.dirty = false;
+                self.conflict = false;
+                self.message = format!("Saved '{}'", name);
+                Some(self.change(None))
+            }
+            Err(SaveError::Conflict | SaveError::Deleted) => {
+                self.conflict = true;
+                None
+            }
+            Err(SaveError::Io(e)) => {
+                self.message = e;
+                None
+            }
+        }
+    }
+
+    /// Writes the edited text back after the file was deleted on disk.
+    fn recreate(&mut self) -> Option<LibraryChange> {
+        let name = self.selected.clone()?;
+        let result = library::create(self.subdir, &name, &self.editor)
+            .map(|()| format!("Recreated '{}'", name));
+        let change = self.finish(result);
+        if change.is_some() {
+            self.loaded_at = library::modified(self.subdir, &name);
+            self.dirty = false;
+            self.conflict = false;
+        }
+        change
+    }
+
+    fn change(&self, renamed: Option<(String, String)>) -> LibraryChange {
+        LibraryChange {
+            subdir: self.subdir,
+            renamed,
+        }
+    }
+
+    fn finish(&mut self, result: Result<String, String>) -> Option<LibraryChange> {
+        match result {
+            Ok(message) => {
+                self.message = message;
+                Some(self.change(None))
+            }
+            Err(e) => {
+                self.message = e;
+                None
+            }
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        tasks: &[String],
+        roles: &[String],
+    ) -> Option<LibraryChange> {
+        if !self.open {
+            return None;
+        }
+        self.check_disk();
+
+        let mut open = self.open;
+        let mut change = None;
+        egui::Window::new("Tasks & Roles")
+            .open(&mut open)
+            .default_size([760.0, 460.0])
+            .show(ctx, |ui| {
+                change = self.contents(ui, tasks, roles);
+            });
+        self.open = open;
+        change
+    }
+
+    fn contents(
+        &mut self,
+        ui: &mut egui::Ui,
+        tasks: &[String],
+        roles: &[String],
+    ) -> Option<LibraryChange> {
+        let mut change = None;
+
+        ui.horizontal(|ui| {
+            for (subdir, label) in [(TASKS, "Tasks"), (ROLES, "Roles")] {
+                if ui.selectable_label(self.subdir == subdir, label).clicked()
+                    && self.subdir != subdir
+                {
+                    if self.dirty {
+                        self.message = "Save or revert the current file first".to_string();
+                    } else {
+                        self.subdir = subdir;
+                        self.select(None);
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.name_input)
+                    .hint_text("Name")
+                    .desired_width(160.0),
+            );
+            let name = self.name_input.trim().to_string();
+            if ui.button("New").clicked() {
+                let result =
+                    library::create(self.subdir, &name, "").map(|()| format!("Created '{}'", name));
+                change = self.finish(result);
+                if change.is_some() {
+                    self.select(Some(name.clone()));
+                }
+            }
+            let has_selection = self.selected.is_some();
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Duplicate"))
+                .clicked()
+            {
+                let result = library::create(self.subdir, &name, &self.editor)
+                    .map(|()| format!("Duplicated as '{}'", name));
+                change = self.finish(result);
+                if change.is_some() {
+                    self.select(Some(name.clone()));
+                }
+            }
+            if ui
+                .add_enabled(has_selection && !self.dirty, egui::Button::new("Rename"))
+                .clicked()
+                && let Some(old_name) = self.selected.clone()
+            {
+                match library::rename(self.subdir, &old_name, &name) {
+                    Ok(()) => {
+                        self.message = format!("Renamed '{}' to '{}'", old_name, name);
+                        change = Some(self.change(Some((old_name, name.clone()))));
+                        self.select(Some(name.clone()));
+                    }
+                    Err(e) => self.message = e,
+                }
+            }
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Delete"))
+                .clicked()
+            {
+                self.confirm_delete = true;
+            }
+        });
+
+        if self.confirm_delete
+            && let Some(name) = self.selected.clone()
+        {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Delete '{}' permanently?", name),
+                );
+                if ui.button("Delete").clicked() {
+                    let result =
+                        library::delete(self.subdir, &name).map(|()| format!("Deleted '{}'", name));
+                    change = self.finish(result);
+                    self.select(None);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.confirm_delete = false;
+                }
+            });
+        }
+
+        if self.conflict {
+            let deleted = self
+                .selected
+                .as_deref()
+                .and_then(|name| library::md_file_path(self.subdir, name))
+                .is_none_or(|path| !path.is_file());
+            ui.horizontal(|ui| {
+                if deleted {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "The file was deleted on disk since it was opened.",
+                    );
+                    if ui.button("Recreate").clicked() {
+                        change = self.recreate();
+                    }
+                    if ui.button("Discard changes").clicked() {
+                        self.select(None);
+                    }
+                } else {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "The file changed on disk since it was opened.",
+                    );
+                    if ui.button("Reload from disk").clicked() {
+                        self.select(self.selected.clone());
+                    }
+                    if ui.button("Overwrite").clicked() {
+                        change = self.save(true);
+                    }
+                }
+            });
+        }
+
+        if !self.message.is_empty() {
+            ui.label(&self.message);
+        }
+        ui.separator();
+
+        let names = if self.subdir == TASKS { tasks } else { roles };
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.set_width(160.0);
+                egui::ScrollArea::vertical()
+                    .id_salt("manager_list")
+                    .show(ui, |ui| {
+                        for name in names {
+                            let selected = self.selected.as_ref() == Some(name);
+                            if ui.selectable_label(selected, name).clicked() && !selected {
+                                if self.dirty {
+                                    self.message =
+                                        "Save or revert the current file first".to_string();
+                                } else {
+                                    self.name_input = name.clone();
+                                    self.select(Some(name.clone()));
+                                }
+                            }
+                        }
+                    });
+            });
+
+            ui.separator();
+
+            if self.selected.is_none() {
+                ui.weak("Select a file to edit");
+                return;
+            }
+
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.dirty, egui::Button::new("Save"))
+                        .clicked()
+                    {
+                        change = self.save(false);
+                    }
+                    if ui
+                        .add_enabled(self.dirty, egui::Button::new("Revert"))
+                        .clicked()
+                    {
+                        self.select(self.selected.clone());
+                    }
+                    if self.dirty {
+                        ui.weak("Unsaved changes");
+                    }
+                });
+
+                ui.columns(2, |columns| {
+                    egui::ScrollArea::vertical().id_salt("manager_editor").show(
+                        &mut columns[0],
+                        |ui| {
+                            let response = ui.add(
+                                egui::TextEdit::multiline(&mut self.editor)
+                                    .code_editor()
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(20),
+                            );
+                            if response.changed() {
+                                self.dirty = true;
+                            }
+                        },
+                    );
+                    egui::ScrollArea::vertical()
+                        .id_salt("manager_preview")
+                        .show(&mut columns[1], |ui| {
+                            markdown::show(ui, &self.editor);
+                        });
+                });
+            });
+        });
+
+        change
+    }
+}
@@ -0,0 +1,226 @@
+//! Minimal Markdown rendering for egui, covering what task and role files use:
+//! headings, paragraphs, emphasis, inline code, code blocks, lists, quotes and rules.
+
+use eframe::egui;
+use egui::text::LayoutJob;
+use egui::{Color32, FontId, Stroke, TextFormat};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+pub fn parser_options() -> Options {
+    Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS
+}
+
+#[derive(Default)]
+struct Renderer {
+    job: LayoutJob,
+    strong: usize,
+    emphasis: usize,
+    strikethrough: usize,
+    heading: Option<HeadingLevel>,
+    quote_depth: usize,
+    /// One entry per open list; `Some(n)` is the next number of an ordered list.
+    lists: Vec<Option<u64>>,
+    code_block: Option<String>,
+    in_metadata: bool,
+}
+
+impl Renderer {
+    fn text_format(&self, ui: &egui::Ui, monospace: bool) -> TextFormat {
+        let body_size = egui::TextStyle::Body.resolve(ui.style()).size;
+        let size = match self.heading {
+            Some(HeadingLevel::H1) => body_size * 1.6,
+            Some(HeadingLevel::H2) => body_size * 1.35,
+            Some(HeadingLevel::H3) => body_size * 1.15,
+            Some(_) => body_size * 1.05,
+            None => body_size,
+        };
+        let font_id = if monospace {
+            FontId::monospace(size)
+        } else {
+            FontId::proportional(size)
+        };
+
+        let visuals = ui.visuals();
+        let color = if self.strong > 0 || self.heading.is_some() {
+            visuals.strong_text_color()
+        } else if self.quote_depth > 0 {
+            visuals.weak_text_color()
+        } else {
+            visuals.text_color()
+        };
+
+        TextFormat {
+            font_id,
+            color,
+            italics: self.emphasis > 0,
+            strikethrough: if self.strikethrough > 0 {
+                Stroke::new(1.0, color)
+            } else {
+                Stroke::NONE
+            },
+            background: if monospace {
+                visuals.code_bg_color
+            } else {
+                Color32::TRANSPARENT
+            },
+            ..Default::default()
+        }
+    }
+
+    fn push_text(&mut self, ui: &egui::Ui, text: &str, monospace: bool) {
+        if self.job.is_empty() && self.quote_depth > 0 {
+            let format = self.text_format(ui, false);
+            self.job.append(&"│ ".repeat(self.quote_depth), 0.0, format);
+        }
+        let format = self.text_format(ui, monospace);
+        self.job.append(text, 0.0, format);
+    }
+
+    fn flush(&mut self, ui: &mut egui::Ui) {
+        if !self.job.is_empty() {
+            let mut job = std::mem::take(&mut self.job);
+            job.wrap.max_width = ui.available_width();
+            ui.label(job);
+        }
+    }
+
+    fn start(&mut self, ui: &mut egui::Ui, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush(ui);
+                ui.add_space(4.0);
+                self.heading = Some(level);
+            }
+            Tag::Paragraph | Tag::TableRow | Tag::TableHead => self.flush(ui),
+            Tag::TableCell if !self.job.is_empty() => self.push_text(ui, " | ", false),
+            Tag::BlockQuote(_) => {
+                self.flush(ui);
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush(ui);
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush(ui);
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush(ui);
+                let indent = "    ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}{}. ", indent, *number - 1)
+                    }
+                    _ => format!("{}• ", indent),
+                };
+                self.push_text(ui, &marker, false);
+            }
+            Tag::Emphasis => self.emphasis += 1,
+            Tag::Strong => self.strong += 1,
+            Tag::Strikethrough => self.strikethrough += 1,
+            Tag::MetadataBlock(_) => self.in_metadata = true,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, ui: &mut egui::Ui, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.flush(ui);
+                self.heading = None;
+            }
+            TagEnd::Paragraph | TagEnd::TableRow | TagEnd::TableHead | TagEnd::Item => {
+                self.flush(ui)
+            }
+            TagEnd::Table => {
+                self.flush(ui);
+                ui.add_space(4.0);
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush(ui);
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code_block.take() {
+                    egui::Frame::new()
+                        .fill(ui.visuals().code_bg_color)
+                        .inner_margin(4.0)
+                        .corner_radius(2.0)
+                        .show(ui, |ui| {
+                            ui.add(
+                                egui::Label::new(
+                                    egui::RichText::new(code.trim_end_matches('\n')).monospace(),
+                                )
+                                .wrap(),
+                            );
+                        });
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush(ui);
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.emphasis = self.emphasis.saturating_sub(1),
+            TagEnd::Strong => self.strong = self.strong.saturating_sub(1),
+            TagEnd::Strikethrough => self.strikethrough = self.strikethrough.saturating_sub(1),
+            TagEnd::MetadataBlock(_) => self.in_metadata = false,
+            _ => {}
+        }
+    }
+}
+
+/// Renders `markdown` into `ui`.
+pub fn show(ui: &mut egui::Ui, markdown: &str) {
+    let mut renderer = Renderer::default();
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(tag) => renderer.start(ui, tag),
+            Event::End(tag) => renderer.end(ui, tag),
+            Event::Text(text) => {
+                if renderer.in_metadata {
+                    continue;
+                }
+                match &mut renderer.code_block {
+                    Some(code) => code.push_str(&text),
+                    None => renderer.push_text(ui, &text, false),
+                }
+            }
+            Event::Code(code) => renderer.push_text(ui, &code, true),
+            Event::SoftBreak => renderer.push_text(ui, " ", false),
+            Event::HardBreak => renderer.push_text(ui, "\n", false),
+            Event::Rule => {
+                renderer.flush(ui);
+                ui.separator();
+            }
+            Event::TaskListMarker(checked) => {
+                renderer.push_text(ui, if checked { "☑ " } else { "☐ " }, false)
+            }
+            _ => {}
+        }
+    }
+    renderer.flush(ui);
+}
+
+/// Renders at most `max_lines` lines of `markdown`, for tooltips.
+pub fn show_excerpt(ui: &mut egui::Ui, markdown: &str, max_lines: usize) {
+    let line_count = markdown.lines().count();
+    if line_count <= max_lines {
+        show(ui, markdown);
+        return;
+    }
+
+    let excerpt = markdown
+        .lines()
+        .take(max_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    show(ui, &excerpt);
+    ui.weak(format!("… {} more lines", line_count - max_lines));
+}
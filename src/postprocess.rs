@@ -0,0 +1,158 @@
+//! User-defined commands the response can be piped through.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Input {
+    /// The response is written to the command's stdin.
+    #[default]
+    Stdin,
+    /// The response is written to a temp file, passed as `{file}` or appended.
+    File,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Output {
+    /// The command's output replaces the response.
+    #[default]
+    Replace,
+    Clipboard,
+    /// The output is saved as a new file in the export directory.
+    File,
+}
+
+/// A named post-processing command, configured as `[[action]]` in `settings.toml`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Action {
+    /// Shown on the button; the command when left out.
+    #[serde(default)]
+    pub name: String,
+    /// Run by the shell, so pipes and quoting work as usual.
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub input: Input,
+    #[serde(default)]
+    pub output: Output,
+    /// Extension of the file written for `output = "file"`.
+    #[serde(default = "default_extension")]
+    pub extension: String,
+}
+
+/// An action run that finished in the background.
+pub struct Finished {
+    pub action: Action,
+    /// The response text the action ran on.
+    pub input: String,
+    pub output: Result<String, String>,
+}
+
+/// Runs finished since the last frame.
+pub type Pending = Arc<Mutex<Vec<Finished>>>;
+
+fn default_extension() -> String {
+    "txt".to_string()
+}
+
+/// `path` as a single shell argument.
+fn quote(path: &str) -> String {
+    if cfg!(windows) {
+        // Windows paths cannot contain double quotes
+        format!("\"{}\"", path)
+    } else {
+        shell_words::quote(path).into_owned()
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// Runs `action` on `text` and returns what the command printed.
+pub fn run(action: &Action, text: &str, working_dir: Option<&Path>) -> Result<String, String> {
+    // Deleted when dropped, after the command finished
+    let mut temp_file = None;
+
+    if action.command.trim().is_empty() {
+        return Err(format!("'{}' has no command", action.name));
+    }
+
+    let mut command = match action.input {
+        Input::Stdin => shell(&action.command),
+        Input::File => {
+            let mut file = tempfile::Builder::new()
+                .prefix("giagui-action-")
+                .suffix(".md")
+                .tempfile()
+                .map_err(|e| format!("Failed to create a temp file: {}", e))?;
+            file.write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to write {}: {}", file.path().display(), e))?;
+            let path = quote(&file.path().display().to_string());
+            temp_file = Some(file.into_temp_path());
+            if action.command.contains("{file}") {
+                // Already quoted placeholders are replaced with their quotes
+                let command = action
+                    .command
+                    .replace("\"{file}\"", &path)
+                    .replace("'{file}'", &path)
+                    .replace("{file}", &path);
+                shell(&command)
+            } else {
+                shell(&format!("{} {}", action.command, path))
+            }
+        }
+    };
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+
+    let child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", action.name, e));
+    let output = child.and_then(|mut child| {
+        if action.input == Input::Stdin
+            && let Some(mut stdin) = child.stdin.take()
+        {
+            // Written from another thread so a command producing output before
+            // reading all its input cannot block on a full pipe
+            let text = text.to_string();
+            thread::spawn(move || {
+                // A command that ignores its input may close stdin early
+                let _ = stdin.write_all(text.as_bytes());
+            });
+        }
+        child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run '{}': {}", action.name, e))
+    });
+    drop(temp_file);
+    let output = output?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "'{}' failed ({}): {}",
+            action.name,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
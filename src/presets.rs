@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ResetPolicy, config_dir};
+
+/// A named snapshot of the request settings (model, task, role, flags, options).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub model: String,
+    pub task: String,
+    pub role: String,
+    pub extra_tasks: Vec<String>,
+    pub extra_roles: Vec<String>,
+    pub tts_enabled: bool,
+    pub tts_language: String,
+    pub use_clipboard: bool,
+    pub browser_output: bool,
+    pub resume: bool,
+    pub options: String,
+    /// What to reset after sending; `None` uses the default from the settings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset: Option<ResetPolicy>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct PresetFile {
+    #[serde(default, rename = "preset")]
+    presets: Vec<Preset>,
+}
+
+fn presets_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("presets.toml"))
+}
+
+pub fn load_presets() -> Vec<Preset> {
+    presets_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| from_toml(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_presets(presets: &[Preset]) -> Result<(), String> {
+    let path = presets_path().ok_or("No config directory available")?;
+    export_presets(&path, presets)
+}
+
+pub fn to_toml(presets: &[Preset]) -> Result<String, String> {
+    let file = PresetFile {
+        presets: presets.to_vec(),
+    };
+    toml::to_string_pretty(&file).map_err(|e| format!("Failed to serialize presets: {}", e))
+}
+
+pub fn from_toml(content: &str) -> Result<Vec<Preset>, String> {
+    let file: PresetFile =
+        toml::from_str(content).map_err(|e| format!("Failed to parse presets: {}", e))?;
+    Ok(file
+        .presets
+        .into_iter()
+        .filter(|preset| !preset.name.trim().is_empty())
+        .collect())
+}
+
+pub fn import_presets(path: &Path) -> Result<Vec<Preset>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    from_toml(&content)
+}
+
+pub fn export_presets(path: &Path, presets: &[Preset]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, to_toml(presets)?)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Inserts `preset`, replacing an existing preset with the same name.
+pub fn upsert(presets: &mut Vec<Preset>, preset: Preset) {
+    match presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+}
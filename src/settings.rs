@@ -0,0 +1,526 @@
+//! Window editing the preferences stored in `settings.toml`.
+
+use eframe::egui;
+
+use crate::appearance;
+use crate::config::{self, Model, Settings, Theme};
+use crate::keymap::{self, Keymap};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Tab {
+    #[default]
+    General,
+    Gia,
+    Models,
+    Attachments,
+    Appearance,
+    Shortcuts,
+    History,
+    Privacy,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 8] = [
+        Tab::General,
+        Tab::Gia,
+        Tab::Models,
+        Tab::Attachments,
+        Tab::Appearance,
+        Tab::Shortcuts,
+        Tab::History,
+        Tab::Privacy,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tab::General => "General",
+            Tab::Gia => "gia executable",
+            Tab::Models => "Models",
+            Tab::Attachments => "Attachments",
+            Tab::Appearance => "Appearance",
+            Tab::Shortcuts => "Shortcuts",
+            Tab::History => "History",
+            Tab::Privacy => "Privacy",
+        }
+    }
+}
+
+pub enum Action {
+    /// Use and save these settings.
+    Apply(Box<Settings>),
+    ForgetTemplateValues,
+}
+
+/// Edits a copy of the settings; nothing changes until Apply or OK.
+#[derive(Default)]
+pub struct Window {
+    pub open: bool,
+    tab: Tab,
+    draft: Settings,
+    keymap: keymap::Editor,
+    /// Lists edited as text, parsed when applying.
+    extra_args: String,
+    tts_languages: String,
+    media_extensions: String,
+}
+
+/// Splits a list typed as `a, b c`.
+fn split_list(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.trim_start_matches('.').to_string())
+        .collect()
+}
+
+impl Window {
+    pub fn open(&mut self, settings: &Settings, tab: Tab) {
+        self.open = true;
+        self.tab = tab;
+        self.load(settings);
+    }
+
+    fn load(&mut self, settings: &Settings) {
+        self.draft = settings.clone();
+        let (keymap, _) = Keymap::new(&settings.shortcuts);
+        self.keymap.load(&keymap);
+        self.extra_args = settings.gia.extra_args.join("\n");
+        self.tts_languages = settings.general.tts_languages.join(", ");
+        self.media_extensions = settings.attachments.media_extensions.join(", ");
+    }
+
+    /// While a shortcut is being recorded, key presses must not trigger commands.
+    pub fn is_capturing(&self) -> bool {
+        self.open && self.keymap.is_capturing()
+    }
+
+    /// The draft with the text-edited lists parsed.
+    fn settings(&self) -> Settings {
+        let mut settings = self.draft.clone();
+        settings.gia.extra_args = self
+            .extra_args
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        settings.general.tts_languages = split_list(&self.tts_languages);
+        settings.attachments.media_extensions = split_list(&self.media_extensions);
+        settings
+            .models
+            .retain(|model| !model.name.trim().is_empty());
+        if settings.models.is_empty() {
+            settings.models = config::default_models();
+        }
+        settings.shortcuts = self.keymap.keymap().overrides();
+        settings
+    }
+
+    /// Restores the defaults of the settings on the current tab.
+    fn reset_tab(&mut self) {
+        let defaults = Settings::default();
+        match self.tab {
+            Tab::General => {
+                self.tts_languages = defaults.general.tts_languages.join(", ");
+                self.draft.general = defaults.general;
+                self.draft.editor = defaults.editor;
+                self.draft.reset = defaults.reset;
+                self.draft.export = defaults.export;
+            }
+            Tab::Gia => {
+                self.extra_args = defaults.gia.extra_args.join("\n");
+                self.draft.gia = defaults.gia;
+            }
+            Tab::Models => self.draft.models = defaults.models,
+            Tab::Attachments => {
+                self.media_extensions = defaults.attachments.media_extensions.join(", ");
+                self.draft.attachments = defaults.attachments;
+            }
+            Tab::Appearance => self.draft.appearance = defaults.appearance,
+            Tab::Shortcuts => self.keymap.load(&Keymap::default()),
+            Tab::History => self.draft.history = defaults.history,
+            Tab::Privacy => self.draft.privacy = defaults.privacy,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Action> {
+        if !self.open {
+            return None;
+        }
+
+        let mut open = self.open;
+        let mut action = None;
+        let mut close = false;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .default_size([640.0, 520.0])
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for tab in Tab::ALL {
+                        ui.selectable_value(&mut self.tab, tab, tab.label());
+                    }
+                });
+                ui.separator();
+
+                let height = (ui.available_height() - 40.0).max(200.0);
+                if self.tab == Tab::Shortcuts {
+                    // Has its own scroll area
+                    ui.allocate_ui(egui::vec2(ui.available_width(), height), |ui| {
+                        self.keymap.ui(ui);
+                    });
+                } else {
+                    egui::ScrollArea::vertical()
+                        .max_height(height)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| match self.tab {
+                            Tab::General => self.general_ui(ui),
+                            Tab::Gia => self.gia_ui(ui),
+                            Tab::Models => self.models_ui(ui),
+                            Tab::Attachments => self.attachments_ui(ui),
+                            Tab::Appearance => self.appearance_ui(ui),
+                            Tab::History => self.history_ui(ui),
+                            Tab::Privacy => {
+                                if self.privacy_ui(ui) {
+                                    action = Some(Action::ForgetTemplateValues);
+                                }
+                            }
+                            Tab::Shortcuts => {}
+                        });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let valid = self.keymap.keymap().conflicts().is_empty();
+                    let hint = "Resolve the shortcut conflicts first";
+                    if ui
+                        .add_enabled(valid, egui::Button::new("OK"))
+                        .on_disabled_hover_text(hint)
+                        .clicked()
+                    {
+                        action = Some(Action::Apply(Box::new(self.settings())));
+                        close = true;
+                    }
+                    if ui
+                        .add_enabled(valid, egui::Button::new("Apply"))
+                        .on_disabled_hover_text(hint)
+                        .clicked()
+                    {
+                        action = Some(Action::Apply(Box::new(self.settings())));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                    if ui
+                        .button("Reset to defaults")
+                        .on_hover_text(format!("Resets the {} tab", self.tab.label()))
+                        .clicked()
+                    {
+                        self.reset_tab();
+                    }
+                });
+            });
+
+        self.open = open && !close;
+        if !self.open {
+            self.keymap.stop_capturing();
+        }
+        action
+    }
+
+    fn general_ui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.draft;
+        ui.heading("At startup");
+        egui::Grid::new("settings_general")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Model");
+                egui::ComboBox::from_id_salt("settings_model")
+                    .selected_text(&settings.general.model)
+                    .show_ui(ui, |ui| {
+                        for model in &settings.models {
+                            ui.selectable_value(
+                                &mut settings.general.model,
+                                model.name.clone(),
+                                &model.label,
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("TTS language");
+                ui.text_edit_singleline(&mut settings.general.tts_language);
+                ui.end_row();
+
+                ui.label("TTS languages offered");
+                ui.text_edit_singleline(&mut self.tts_languages);
+                ui.end_row();
+            });
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut settings.general.use_clipboard, "Clipboard input (-c)");
+            ui.checkbox(&mut settings.general.browser_output, "Browser output");
+            ui.checkbox(&mut settings.general.resume, "Resume conversation (-R)");
+            ui.checkbox(&mut settings.general.tts_enabled, "Text-to-speech");
+        });
+
+        ui.separator();
+        ui.heading("After sending");
+        let reset = &mut settings.reset;
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut reset.clear_prompt, "Clear prompt");
+            ui.checkbox(&mut reset.clear_task, "Clear task");
+            ui.checkbox(&mut reset.clear_role, "Clear role");
+            ui.checkbox(&mut reset.clear_clipboard, "Turn off clipboard input");
+            ui.checkbox(&mut reset.clear_attachments, "Remove attachments");
+            ui.checkbox(&mut reset.clear_options, "Clear all options");
+            ui.checkbox(&mut reset.enable_resume, "Turn on resume");
+        });
+
+        ui.separator();
+        egui::Grid::new("settings_files")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("External editor");
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.editor)
+                        .hint_text("$VISUAL / $EDITOR")
+                        .desired_width(320.0),
+                );
+                ui.end_row();
+
+                ui.label("Export directory");
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.export.directory).desired_width(320.0),
+                );
+                ui.end_row();
+
+                ui.label("Export file name");
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.export.file_name).desired_width(320.0),
+                )
+                .on_hover_text("{date} {time} (UTC) {model} {task} {role} {title}");
+                ui.end_row();
+            });
+    }
+
+    fn gia_ui(&mut self, ui: &mut egui::Ui) {
+        let gia = &mut self.draft.gia;
+        egui::Grid::new("settings_gia")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Executable");
+                ui.add(
+                    egui::TextEdit::singleline(&mut gia.executable)
+                        .hint_text("gia")
+                        .desired_width(320.0),
+                );
+                ui.end_row();
+
+                ui.label("Working directory");
+                ui.add(
+                    egui::TextEdit::singleline(&mut gia.working_dir)
+                        .hint_text("giagui's directory")
+                        .desired_width(320.0),
+                )
+                .on_hover_text("Used unless a workspace sets one");
+                ui.end_row();
+
+                ui.label("Extra arguments");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.extra_args)
+                        .hint_text("One per line")
+                        .desired_rows(4)
+                        .desired_width(320.0),
+                )
+                .on_hover_text("Passed before the options of every request");
+                ui.end_row();
+            });
+    }
+
+    fn models_ui(&mut self, ui: &mut egui::Ui) {
+        let models = &mut self.draft.models;
+        let mut remove = None;
+        let mut swap = None;
+        let count = models.len();
+        egui::Grid::new("settings_models")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Name (--model)");
+                ui.strong("Label");
+                ui.end_row();
+                for (index, model) in models.iter_mut().enumerate() {
+                    ui.text_edit_singleline(&mut model.name);
+                    ui.text_edit_singleline(&mut model.label);
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(index > 0, egui::Button::new("⏶").small())
+                            .clicked()
+                        {
+                            swap = Some(index - 1);
+                        }
+                        if ui
+                            .add_enabled(index + 1 < count, egui::Button::new("⏷").small())
+                            .clicked()
+                        {
+                            swap = Some(index);
+                        }
+                        if ui
+                            .add_enabled(count > 1, egui::Button::new("🗑").small())
+                            .clicked()
+                        {
+                            remove = Some(index);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = swap {
+            models.swap(index, index + 1);
+        }
+        if let Some(index) = remove {
+            models.remove(index);
+        }
+        if ui.button("Add model").clicked() {
+            models.push(Model {
+                name: String::new(),
+                label: String::new(),
+            });
+        }
+    }
+
+    fn attachments_ui(&mut self, ui: &mut egui::Ui) {
+        let attachments = &mut self.draft.attachments;
+        egui::Grid::new("settings_attachments")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Media extensions (-i)");
+                ui.add(egui::TextEdit::singleline(&mut self.media_extensions).desired_width(320.0))
+                    .on_hover_text("Other files are attached as text with -f");
+                ui.end_row();
+
+                ui.label("Files per folder");
+                ui.add(egui::DragValue::new(&mut attachments.max_files).suffix(" (0 = all)"));
+                ui.end_row();
+            });
+        ui.checkbox(
+            &mut attachments.recurse_directories,
+            "Include subfolders of dropped folders",
+        );
+        ui.checkbox(
+            &mut attachments.skip_hidden,
+            "Skip hidden files and folders (.git, ...)",
+        );
+    }
+
+    fn appearance_ui(&mut self, ui: &mut egui::Ui) {
+        let appearance = &mut self.draft.appearance;
+        let font_ui = |ui: &mut egui::Ui, font: &mut String| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(font)
+                        .hint_text("Default")
+                        .desired_width(260.0),
+                )
+                .on_hover_text("A built-in font or the path of a .ttf, .otf or .ttc file");
+                ui.menu_button("Built-in", |ui| {
+                    if ui.button("Default").clicked() {
+                        font.clear();
+                        ui.close_menu();
+                    }
+                    for name in appearance::BUILTIN_FONTS {
+                        if ui.button(*name).clicked() {
+                            *font = name.to_string();
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+        };
+        egui::Grid::new("settings_appearance")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Theme");
+                ui.horizontal(|ui| {
+                    for theme in Theme::ALL {
+                        ui.radio_value(&mut appearance.theme, theme, theme.label());
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Font");
+                font_ui(ui, &mut appearance.proportional_font);
+                ui.end_row();
+
+                ui.label("Monospace font");
+                font_ui(ui, &mut appearance.monospace_font);
+                ui.end_row();
+
+                ui.label("Zoom");
+                ui.add(
+                    egui::Slider::new(
+                        &mut appearance.zoom,
+                        appearance::MIN_ZOOM..=appearance::MAX_ZOOM,
+                    )
+                    .step_by(0.1)
+                    .custom_formatter(|zoom, _| format!("{:.0}%", zoom * 100.0)),
+                )
+                .on_hover_text(
+                    "Ctrl+Plus, Ctrl+Minus and Ctrl+0 are the Zoom in, Zoom out and \
+                     Reset zoom shortcuts; unbinding them leaves only this slider",
+                );
+                ui.end_row();
+
+                ui.label("Prompt editor height");
+                ui.add(
+                    egui::DragValue::new(&mut appearance.prompt_rows)
+                        .range(1..=20)
+                        .suffix(" lines"),
+                );
+                ui.end_row();
+            });
+        ui.checkbox(
+            &mut appearance.cjk_fallback,
+            "Use an installed CJK font for Chinese, Japanese and Korean text",
+        );
+        ui.checkbox(&mut appearance.response_monospace, "Monospace response");
+        ui.checkbox(
+            &mut appearance.show_preview,
+            "Show the task/role preview at startup",
+        );
+    }
+
+    fn history_ui(&mut self, ui: &mut egui::Ui) {
+        let history = &mut self.draft.history;
+        egui::Grid::new("settings_history")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Responses kept");
+                ui.add(egui::DragValue::new(&mut history.max_turns).suffix(" (0 = all)"))
+                    .on_hover_text("Older responses can no longer be diffed or exported");
+                ui.end_row();
+
+                ui.label("Conversations listed");
+                ui.add(egui::DragValue::new(&mut history.max_conversations).suffix(" (0 = all)"));
+                ui.end_row();
+            });
+    }
+
+    /// Returns true when the remembered template values should be deleted.
+    fn privacy_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let privacy = &mut self.draft.privacy;
+        ui.checkbox(
+            &mut privacy.remember_template_values,
+            "Remember the values entered for placeholders",
+        );
+        ui.checkbox(
+            &mut privacy.show_conversation_previews,
+            "Show the first message of unnamed conversations",
+        );
+        ui.add_space(8.0);
+        let forget = ui
+            .button("Forget remembered placeholder values")
+            .on_hover_text("Deletes template-values.toml now")
+            .clicked();
+        ui.weak("Conversations are stored by gia in ~/.gia/conversations.");
+        forget
+    }
+}
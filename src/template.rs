@@ -0,0 +1,398 @@
+//! `{{variable}}` placeholders in prompts and task files.
+//!
+//! - `{{name}}` asks for a value before sending
+//! - `{{name=default}}` prefills the field with `default`
+//! - `{{name|first|second}}` offers a choice
+//! - `{{clipboard}}`, `{{date}}` (local), `{{selection}}` and `{{file:path}}` are
+//!   filled in automatically
+//! - `\{{` is a literal `{{`
+
+use arboard::Clipboard;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::config_dir;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Placeholder {
+    Variable {
+        name: String,
+        default: Option<String>,
+        choices: Vec<String>,
+    },
+    Clipboard,
+    Date,
+    Selection,
+    File(String),
+}
+
+fn parse_placeholder(inner: &str) -> Option<Placeholder> {
+    let inner = inner.trim();
+    match inner {
+        "clipboard" => return Some(Placeholder::Clipboard),
+        "date" => return Some(Placeholder::Date),
+        "selection" => return Some(Placeholder::Selection),
+        _ => {}
+    }
+    if let Some(path) = inner.strip_prefix("file:") {
+        return Some(Placeholder::File(path.trim().to_string()));
+    }
+
+    let (name, default, choices) = if let Some((name, default)) = inner.split_once('=') {
+        (name, Some(default.trim().to_string()), Vec::new())
+    } else if let Some((name, choices)) = inner.split_once('|') {
+        let choices: Vec<String> = choices.split('|').map(|c| c.trim().to_string()).collect();
+        (name, None, choices)
+    } else {
+        (inner, None, Vec::new())
+    };
+
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ' '));
+    valid.then(|| Placeholder::Variable {
+        name: name.to_string(),
+        default,
+        choices,
+    })
+}
+
+/// Calls `on_placeholder` for every placeholder and returns the text with each
+/// replaced by the callback's result.
+fn replace(text: &str, mut on_placeholder: impl FnMut(&Placeholder) -> String) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        if let Some(before) = rest[..start].strip_suffix('\\') {
+            output.push_str(before);
+            output.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + length];
+        output.push_str(&rest[..start]);
+        match parse_placeholder(inner) {
+            Some(placeholder) => output.push_str(&on_placeholder(&placeholder)),
+            None => output.push_str(&rest[start..start + length + 4]),
+        }
+        rest = &rest[start + length + 4..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Whether rendering changes `text`: it has placeholders or `\{{` escapes.
+pub fn has_template_syntax(text: &str) -> bool {
+    text.contains("\\{{") || has_placeholders(text)
+}
+
+fn has_placeholders(text: &str) -> bool {
+    let mut found = false;
+    replace(text, |_| {
+        found = true;
+        String::new()
+    });
+    found
+}
+
+/// A value the user has to fill in.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    pub choices: Vec<String>,
+    pub value: String,
+    /// Key of the template the value is remembered for.
+    key: String,
+}
+
+/// Collects the user-supplied variables of `sources` (template key, text),
+/// prefilled from remembered values or the placeholder defaults.
+pub fn fields(sources: &[(String, &str)], remembered: &StoredValues) -> Vec<Field> {
+    let mut fields: Vec<Field> = Vec::new();
+
+    for (key, text) in sources {
+        replace(text, |placeholder| {
+            if let Placeholder::Variable {
+                name,
+                default,
+                choices,
+            } = placeholder
+                && !fields.iter().any(|field| field.name == *name)
+            {
+                let value = remembered
+                    .get(key)
+                    .and_then(|values| values.get(name))
+                    .cloned()
+                    .or_else(|| default.clone())
+                    .or_else(|| choices.first().cloned())
+                    .unwrap_or_default();
+                fields.push(Field {
+                    name: name.clone(),
+                    choices: choices.clone(),
+                    value,
+                    key: key.clone(),
+                });
+            }
+            String::new()
+        });
+    }
+    fields
+}
+
+/// Key the values of a prompt's placeholders are remembered under: the names
+/// of its placeholders, so they survive edits to the rest of the prompt.
+pub fn prompt_key(prompt: &str) -> String {
+    let mut names: Vec<String> = Vec::new();
+    replace(prompt, |placeholder| {
+        if let Placeholder::Variable { name, .. } = placeholder
+            && !names.contains(name)
+        {
+            names.push(name.clone());
+        }
+        String::new()
+    });
+    names.sort();
+    format!("prompt:{}", names.join(","))
+}
+
+/// Values of the automatic placeholders that depend on the UI.
+pub struct Builtins {
+    pub selection: String,
+    /// Directory relative `{{file:path}}` placeholders are resolved against.
+    pub base_dir: PathBuf,
+}
+
+/// Fills in the placeholders. Fails if a `{{file:path}}` cannot be read, so
+/// the error is not sent to the model in place of the file.
+pub fn render(text: &str, fields: &[Field], builtins: &Builtins) -> Result<String, String> {
+    let mut error = None;
+    let rendered = replace(text, |placeholder| match placeholder {
+        Placeholder::Variable { name, .. } => fields
+            .iter()
+            .find(|field| field.name == *name)
+            .map(|field| field.value.clone())
+            .unwrap_or_default(),
+        Placeholder::Clipboard => Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .unwrap_or_default(),
+        Placeholder::Date => today(),
+        Placeholder::Selection => builtins.selection.clone(),
+        Placeholder::File(path) => {
+            let path = builtins.base_dir.join(path);
+            fs::read_to_string(&path).unwrap_or_else(|e| {
+                error.get_or_insert_with(|| format!("Failed to read {}: {}", path.display(), e));
+                String::new()
+            })
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(rendered),
+    }
+}
+
+/// Today's date in the local time zone as `YYYY-MM-DD`, so it is the user's
+/// day also around midnight.
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Date (UTC) of `secs` since the Unix epoch as `YYYY-MM-DD`.
+pub fn format_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Last-used values per template key (`prompt:<placeholder names>` or
+/// `task:<name>`).
+pub type StoredValues = HashMap<String, HashMap<String, String>>;
+
+#[derive(Default, Serialize, Deserialize)]
+struct ValuesFile {
+    #[serde(default)]
+    templates: StoredValues,
+}
+
+fn values_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("template-values.toml"))
+}
+
+pub fn load_values() -> StoredValues {
+    values_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| toml::from_str::<ValuesFile>(&content).ok())
+        .map(|file| file.templates)
+        .unwrap_or_default()
+}
+
+/// Deletes the remembered values from disk.
+pub fn forget_values() -> Result<(), String> {
+    let Some(path) = values_path().filter(|path| path.exists()) else {
+        return Ok(());
+    };
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+}
+
+/// Remembers the values in `fields` and writes them to disk.
+pub fn remember(values: &mut StoredValues, fields: &[Field]) -> Result<(), String> {
+    for field in fields {
+        values
+            .entry(field.key.clone())
+            .or_default()
+            .insert(field.name.clone(), field.value.clone());
+    }
+
+    let path = values_path().ok_or("No config directory available")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = toml::to_string_pretty(&ValuesFile {
+        templates: values.clone(),
+    })
+    .map_err(|e| format!("Failed to serialize template values: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtins(base_dir: PathBuf) -> Builtins {
+        Builtins {
+            selection: "selected text".to_string(),
+            base_dir,
+        }
+    }
+
+    fn fields_of(text: &str) -> Vec<Field> {
+        fields(&[("prompt".to_string(), text)], &StoredValues::new())
+    }
+
+    #[test]
+    fn defaults_and_choices_prefill_the_fields() {
+        let fields = fields_of("{{name}} {{tone=friendly}} {{length|short|long}} {{name}}");
+        let summary: Vec<(&str, &str, usize)> = fields
+            .iter()
+            .map(|field| {
+                (
+                    field.name.as_str(),
+                    field.value.as_str(),
+                    field.choices.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("name", "", 0),
+                ("tone", "friendly", 0),
+                ("length", "short", 2)
+            ]
+        );
+        assert_eq!(fields[2].choices, ["short", "long"]);
+    }
+
+    #[test]
+    fn remembered_values_win_over_defaults() {
+        let mut remembered = StoredValues::new();
+        remembered
+            .entry("prompt".to_string())
+            .or_default()
+            .insert("tone".to_string(), "formal".to_string());
+        let fields = fields(&[("prompt".to_string(), "{{tone=friendly}}")], &remembered);
+        assert_eq!(fields[0].value, "formal");
+    }
+
+    #[test]
+    fn render_fills_in_values_and_builtins() {
+        let mut fields = fields_of("Hi {{ name }}, {{tone=friendly}}: {{selection}}");
+        fields[0].value = "Ada".to_string();
+        let rendered = render(
+            "Hi {{ name }}, {{tone=friendly}}: {{selection}}",
+            &fields,
+            &builtins(PathBuf::new()),
+        );
+        assert_eq!(rendered.unwrap(), "Hi Ada, friendly: selected text");
+    }
+
+    #[test]
+    fn escaped_braces_are_literal() {
+        let text = r"\{{name}} and {{name}}";
+        let mut fields = fields_of(text);
+        assert_eq!(fields.len(), 1);
+        fields[0].value = "x".to_string();
+        let rendered = render(text, &fields, &builtins(PathBuf::new())).unwrap();
+        assert_eq!(rendered, "{{name}} and x");
+
+        assert!(has_template_syntax(r"only \{{escaped}}"));
+        assert!(!has_placeholders(r"only \{{escaped}}"));
+        assert_eq!(
+            render(r"only \{{escaped}}", &[], &builtins(PathBuf::new())).unwrap(),
+            "only {{escaped}}"
+        );
+    }
+
+    #[test]
+    fn unterminated_and_invalid_placeholders_are_kept() {
+        let builtins = builtins(PathBuf::new());
+        assert!(fields_of("a {{name").is_empty());
+        assert_eq!(render("a {{name", &[], &builtins).unwrap(), "a {{name");
+        assert_eq!(
+            render("{{date}} {{b", &[], &builtins).unwrap(),
+            format!("{} {{{{b", today())
+        );
+        assert_eq!(
+            render("{{a.b}} {{}}", &[], &builtins).unwrap(),
+            "{{a.b}} {{}}"
+        );
+        assert!(!has_template_syntax("plain {text} and }}"));
+    }
+
+    #[test]
+    fn file_placeholders_read_relative_to_the_base_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "from file").unwrap();
+        let builtins = builtins(dir.path().to_path_buf());
+        assert_eq!(
+            render("{{file: notes.txt}}", &[], &builtins).unwrap(),
+            "from file"
+        );
+        let error = render("{{file:missing.txt}}", &[], &builtins).unwrap_err();
+        assert!(error.contains("missing.txt"), "{}", error);
+    }
+
+    #[test]
+    fn prompt_key_ignores_text_and_order() {
+        assert_eq!(prompt_key("{{b}} then {{a=1}}"), "prompt:a,b");
+        assert_eq!(prompt_key("other {{a}} words {{b|x|y}}"), "prompt:a,b");
+        assert_eq!(prompt_key("{{date}} only"), "prompt:");
+    }
+
+    #[test]
+    fn format_date_handles_leap_days() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_735_689_599), "2024-12-31");
+    }
+}
@@ -0,0 +1,100 @@
+//! Notices changes to the task and role directories so the lists can be reloaded.
+
+use eframe::egui;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct LibraryWatcher {
+    changed: Arc<AtomicBool>,
+    // Kept alive for as long as the watcher should run
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl LibraryWatcher {
+    /// Watches `dirs` with the native file watcher, falling back to polling when
+    /// that is unavailable or a directory does not exist yet.
+    pub fn start(dirs: Vec<PathBuf>, ctx: egui::Context) -> LibraryWatcher {
+        let changed = Arc::new(AtomicBool::new(false));
+
+        let watcher = native_watcher(&dirs, Arc::clone(&changed), ctx.clone());
+        if watcher.is_none() {
+            poll(dirs, Arc::clone(&changed), ctx);
+        }
+
+        LibraryWatcher {
+            changed,
+            _watcher: watcher,
+        }
+    }
+
+    /// Returns whether anything changed since the last call.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+fn native_watcher(
+    dirs: &[PathBuf],
+    changed: Arc<AtomicBool>,
+    ctx: egui::Context,
+) -> Option<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+        {
+            changed.store(true, Ordering::Relaxed);
+            ctx.request_repaint();
+        }
+    })
+    .ok()?;
+
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::Recursive).ok()?;
+    }
+    Some(watcher)
+}
+
+fn poll(dirs: Vec<PathBuf>, changed: Arc<AtomicBool>, ctx: egui::Context) {
+    thread::spawn(move || {
+        let mut last = snapshot(&dirs);
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = snapshot(&dirs);
+            if current != last {
+                last = current;
+                changed.store(true, Ordering::Relaxed);
+                ctx.request_repaint();
+            }
+        }
+    });
+}
+
+fn snapshot(dirs: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut entries = Vec::new();
+    for dir in dirs {
+        collect(dir, &mut entries);
+    }
+    entries.sort();
+    entries
+}
+
+fn collect(dir: &Path, entries: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+    if let Ok(read_dir) = fs::read_dir(dir) {
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                collect(&path, entries);
+            } else {
+                let modified = entry.metadata().and_then(|meta| meta.modified()).ok();
+                entries.push((path, modified));
+            }
+        }
+    }
+}
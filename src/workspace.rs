@@ -0,0 +1,76 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const WORKSPACE_FILE: &str = ".giagui.toml";
+
+/// Per-project defaults read from a `.giagui.toml` file.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceConfig {
+    pub name: Option<String>,
+    pub model: Option<String>,
+    pub task: Option<String>,
+    pub role: Option<String>,
+    pub tts_enabled: Option<bool>,
+    pub tts_language: Option<String>,
+    pub use_clipboard: Option<bool>,
+    pub browser_output: Option<bool>,
+    pub resume: Option<bool>,
+    pub options: Option<String>,
+    /// Files or directories attached as `-f`/`-i` options, relative to the workspace root.
+    pub attach: Vec<String>,
+    /// Working directory for gia, relative to the workspace root. Defaults to the root.
+    pub working_dir: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config: WorkspaceConfig,
+}
+
+impl Workspace {
+    /// Looks for a workspace file in `start` and its parent directories.
+    pub fn discover(start: &Path) -> Option<Result<Workspace, String>> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(WORKSPACE_FILE))
+            .find(|path| path.is_file())
+            .map(|path| Self::load(&path))
+    }
+
+    fn load(path: &Path) -> Result<Workspace, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let config: WorkspaceConfig = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        Ok(Workspace { root, config })
+    }
+
+    pub fn name(&self) -> String {
+        self.config.name.clone().unwrap_or_else(|| {
+            self.root
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.root.display().to_string())
+        })
+    }
+
+    pub fn working_dir(&self) -> PathBuf {
+        match &self.config.working_dir {
+            Some(dir) => self.root.join(dir),
+            None => self.root.clone(),
+        }
+    }
+
+    pub fn attachments(&self) -> Vec<PathBuf> {
+        self.config
+            .attach
+            .iter()
+            .map(|path| self.root.join(path))
+            .collect()
+    }
+}